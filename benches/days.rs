@@ -0,0 +1,32 @@
+use adventofcode2023::{
+    bench,
+    input::{self, InputSource},
+    registry,
+};
+use std::env;
+
+// Run with `cargo bench`, optionally setting AOC_BENCH_RUNS (default 10)
+fn main() {
+    let runs = env::var("AOC_BENCH_RUNS")
+        .ok()
+        .and_then(|r| r.parse::<usize>().ok())
+        .unwrap_or(10);
+    let inputs_dir = input::default_inputs_dir();
+
+    let mut benches = Vec::new();
+    for day in registry::days() {
+        let input = match InputSource::for_day(day.day, None, &inputs_dir).read() {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Skipping day {}: {}", day.day, e);
+                continue;
+            }
+        };
+        match bench::bench_day(day, &input, runs) {
+            Ok(b) => benches.push(b),
+            Err(e) => eprintln!("Day {} failed: {}", day.day, e),
+        }
+    }
+
+    println!("{}", bench::bench_table(&benches));
+}
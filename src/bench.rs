@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use crate::{
+    daylib::{Day, Result, Timings},
+    runner::table,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        samples.sort_unstable();
+        let middle = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (*samples.get(middle.checked_sub(1)?)? + samples[middle]) / 2
+        } else {
+            samples[middle]
+        };
+
+        Some(Stats {
+            min: *samples.first()?,
+            median,
+            max: *samples.last()?,
+        })
+    }
+}
+
+pub struct DayBench {
+    pub day: u8,
+    pub runs: usize,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+// Each run parses the input again, so the parse timing is sampled as often as the parts
+pub fn bench_day(day: &Day, input: &str, runs: usize) -> Result<DayBench> {
+    let mut samples = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let mut timings = Timings::default();
+        (day.run)(input, &mut timings)?;
+        samples.push(timings);
+    }
+
+    let stats = |stage: fn(&Timings) -> Duration| {
+        Stats::from_samples(samples.iter().map(stage).collect())
+            .ok_or("Must benchmark at least one run")
+    };
+
+    Ok(DayBench {
+        day: day.day,
+        runs,
+        parse: stats(|t| t.parse)?,
+        part1: stats(|t| t.part1)?,
+        part2: stats(|t| t.part2)?,
+    })
+}
+
+pub fn bench_table(benches: &[DayBench]) -> String {
+    let mut rows = vec![["Day", "Stage", "Runs", "Min", "Median", "Max"].map(String::from)];
+    for bench in benches {
+        for (stage, stats) in [
+            ("parse", bench.parse),
+            ("part 1", bench.part1),
+            ("part 2", bench.part2),
+        ] {
+            rows.push([
+                bench.day.to_string(),
+                stage.to_owned(),
+                bench.runs.to_string(),
+                format!("{:.2?}", stats.min),
+                format!("{:.2?}", stats.median),
+                format!("{:.2?}", stats.max),
+            ]);
+        }
+    }
+
+    table(&rows)
+}
+
+#[test]
+fn test_stats() {
+    let ms = Duration::from_millis;
+    assert_eq!(Stats::from_samples(vec![]), None);
+    assert_eq!(
+        Stats::from_samples(vec![ms(5), ms(1), ms(3)]),
+        Some(Stats {
+            min: ms(1),
+            median: ms(3),
+            max: ms(5)
+        })
+    );
+    assert_eq!(
+        Stats::from_samples(vec![ms(4), ms(1), ms(2), ms(9)]),
+        Some(Stats {
+            min: ms(1),
+            median: ms(3),
+            max: ms(9)
+        })
+    );
+}
+
+#[test]
+fn test_bench_day() {
+    let day = crate::registry::find(2023, 1).unwrap();
+    let bench = bench_day(day, "1abc2\npqr3stu8vwx", 3).unwrap();
+    assert_eq!(bench.runs, 3);
+    assert!(bench.part1.min <= bench.part1.median && bench.part1.median <= bench.part1.max);
+    assert!(bench_day(day, "1abc2", 0).is_err());
+}
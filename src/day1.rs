@@ -1,60 +1,71 @@
+use crate::daylib::{self, Day, Error, Part, Solver};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::error::Error;
 
-use crate::daylib::{DayResult, Parts};
+pub const DAY: Day = Day::new::<Day1>(2023, 1);
 
-pub fn day1(input: &str) -> DayResult {
-    let part1 = part1(input)?;
-    let part2 = part2(input)?;
+struct Day1;
 
-    Ok(Parts {
-        part1: Box::new(format!("The sum of the digits is {}", part1)),
-        part2: Box::new(format!("The sum of the wordy digits is {}", part2)),
-    })
+impl Solver for Day1 {
+    type Input = String;
+
+    fn parse(input: &str) -> daylib::Result<Self::Input> {
+        Ok(input.to_owned())
+    }
+
+    fn part1(input: &Self::Input) -> daylib::Result<Part> {
+        Ok(Part::described(part1(input)?, "The sum of the digits"))
+    }
+
+    fn part2(input: &Self::Input) -> daylib::Result<Part> {
+        Ok(Part::described(
+            part2(input)?,
+            "The sum of the wordy digits",
+        ))
+    }
 }
 
-fn part1(input: &str) -> Result<u32, Box<dyn Error>> {
+fn part1(input: &str) -> daylib::Result<u32> {
     let numbers = input
         .lines()
-        .map(parse_line)
-        .collect::<Result<Vec<u32>, _>>()?;
+        .enumerate()
+        .map(|(i, line)| parse_line(line).map_err(|e| e.on_line(i + 1)))
+        .collect::<daylib::Result<Vec<u32>>>()?;
 
     Ok(numbers.into_iter().sum())
 }
 
-fn parse_line(line: &str) -> Result<u32, Box<dyn Error>> {
-    let mut first_digit: Option<char> = None;
-    let mut last_digit: Option<char> = None;
+fn parse_line(line: &str) -> daylib::Result<u32> {
+    let mut first_digit: Option<u32> = None;
+    let mut last_digit: Option<u32> = None;
 
     for c in line.chars() {
         if c.is_ascii_digit() {
-            first_digit = Some(c);
+            first_digit = c.to_digit(10);
             break;
         }
     }
 
     for c in line.chars().rev() {
         if c.is_ascii_digit() {
-            last_digit = Some(c);
+            last_digit = c.to_digit(10);
             break;
         }
     }
 
     if let (Some(first), Some(last)) = (first_digit, last_digit) {
-        let concatenated = format!("{}{}", first, last);
-        let parsed = concatenated.parse::<u32>()?;
-        Ok(parsed)
+        Ok(first * 10 + last)
     } else {
-        Err(format!("Unable to parse two digits from line '{}'", line).into())
+        Err(Error::parse(1, 1, line, "unable to find any digits"))
     }
 }
 
-fn part2(input: &str) -> Result<u32, Box<dyn Error>> {
+fn part2(input: &str) -> daylib::Result<u32> {
     let numbers = input
         .lines()
-        .map(parse_line_part2)
-        .collect::<Result<Vec<u32>, _>>()?;
+        .enumerate()
+        .map(|(i, line)| parse_line_part2(line).map_err(|e| e.on_line(i + 1)))
+        .collect::<daylib::Result<Vec<u32>>>()?;
 
     Ok(numbers.into_iter().sum())
 }
@@ -77,14 +88,14 @@ fn find_first_in_line(line: &str, from: usize) -> Option<(usize, String)> {
     Some((first.start(), first.as_str().to_owned()))
 }
 
-fn parse_line_part2(line: &str) -> Result<u32, Box<dyn Error>> {
+fn parse_line_part2(line: &str) -> daylib::Result<u32> {
     let (first_index, first_string) = find_first_in_line(line, 0)
-        .ok_or_else(|| format!("Unable to match any digits in '{}'", line))?;
+        .ok_or_else(|| Error::parse(1, 1, line, "unable to match any digits"))?;
 
     let mut start_index = first_index + 1;
     let mut last_string = first_string.clone();
 
-    while let Some((i, s)) = find_first_in_line(&line, start_index) {
+    while let Some((i, s)) = find_first_in_line(line, start_index) {
         start_index = i + 1;
         last_string = s;
     }
@@ -92,12 +103,10 @@ fn parse_line_part2(line: &str) -> Result<u32, Box<dyn Error>> {
     let first = match_to_num(&first_string)?;
     let last = match_to_num(&last_string)?;
 
-    let concatenated = format!("{}{}", first, last);
-    let parsed = concatenated.parse::<u32>()?;
-    Ok(parsed)
+    Ok(first * 10 + last)
 }
 
-fn match_to_num(m: &str) -> Result<u32, Box<dyn Error>> {
+fn match_to_num(m: &str) -> daylib::Result<u32> {
     match m {
         "1" | "one" => Ok(1),
         "2" | "two" => Ok(2),
@@ -168,6 +177,22 @@ fn test_part2_line3() {
     let result = part2(input).unwrap();
     assert_eq!(result, 22);
 }
+#[test]
+fn test_line_without_digits() {
+    assert_eq!(
+        part1("1abc2\nabc").unwrap_err().to_string(),
+        "Parse error at line 2, column 1: unable to find any digits
+2 | abc
+  | ^"
+    );
+    assert_eq!(
+        part2("1abc2\nabc").unwrap_err().to_string(),
+        "Parse error at line 2, column 1: unable to match any digits
+2 | abc
+  | ^"
+    );
+}
+
 #[test]
 fn test_part2_line4() {
     let result = part2("eightwo").unwrap();
@@ -1,23 +1,32 @@
-use crate::daylib::{DayResult, Parts, Result};
+use crate::daylib::{parse_number, Day, Error, Part, Result, Solver};
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 
-pub fn day2(input: &str) -> DayResult {
-    let part1 = part1(input)?;
-    let part2 = part2(input)?;
+pub const DAY: Day = Day::new::<Day2>(2023, 2);
 
-    Ok(Parts {
-        part1: Box::new(format!("Sum of possible game IDs is {}", part1)),
-        part2: Box::new(format!("Sum of powers of minimal sets is {}", part2)),
-    })
+struct Day2;
+
+impl Solver for Day2 {
+    type Input = Vec<Game>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1(games: &Self::Input) -> Result<Part> {
+        Ok(Part::described(part1(games), "Sum of possible game IDs"))
+    }
+
+    fn part2(games: &Self::Input) -> Result<Part> {
+        Ok(Part::described(
+            part2(games),
+            "Sum of powers of minimal sets",
+        ))
+    }
 }
 
-fn part2(input: &str) -> Result<u32> {
-    Ok(parse_input(input)?
-        .iter()
-        .map(fewest_cubes)
-        .map(|b| b.power())
-        .sum())
+fn part2(games: &[Game]) -> u32 {
+    games.iter().map(fewest_cubes).map(|b| b.power()).sum()
 }
 
 fn fewest_cubes(game: &Game) -> Bag {
@@ -49,6 +58,7 @@ impl Bag {
     }
 }
 
+#[derive(Debug)]
 struct Game {
     id: u32,
     bags: Vec<Bag>,
@@ -60,56 +70,68 @@ impl Game {
     }
 }
 
-fn parse_grab(input: &str) -> Result<Bag> {
+fn parse_grab(line: &str, offset: usize, grab: &str) -> Result<Bag> {
     lazy_static! {
         static ref BLUE: Regex = Regex::new(r"(\d+) blue").unwrap();
         static ref RED: Regex = Regex::new(r"(\d+) red").unwrap();
         static ref GREEN: Regex = Regex::new(r"(\d+) green").unwrap();
     }
 
-    let blue = parse_from_capture(BLUE.captures(input))?;
-    let red = parse_from_capture(RED.captures(input))?;
-    let green = parse_from_capture(GREEN.captures(input))?;
+    let blue = parse_from_capture(BLUE.captures(grab), line, offset)?;
+    let red = parse_from_capture(RED.captures(grab), line, offset)?;
+    let green = parse_from_capture(GREEN.captures(grab), line, offset)?;
 
-    return Ok(Bag::new(red, green, blue));
+    Ok(Bag::new(red, green, blue))
 }
 
-fn parse_grabs(input: &str) -> Result<Vec<Bag>> {
-    input.split(";").map(|i| parse_grab(i)).collect()
+fn parse_grabs(line: &str) -> Result<Vec<Bag>> {
+    let mut offset = 0;
+    let mut bags = Vec::new();
+    for grab in line.split(';') {
+        bags.push(parse_grab(line, offset, grab)?);
+        offset += grab.len() + 1;
+    }
+    Ok(bags)
 }
 
-fn parse_game(input: &str) -> Result<Game> {
+fn parse_game(line: &str) -> Result<Game> {
     lazy_static! {
         static ref GAME: Regex = Regex::new(r"Game (\d+)").unwrap();
     }
 
-    let id = parse_from_capture(GAME.captures(input))?;
-    let bags = parse_grabs(input)?;
+    let game = GAME
+        .captures(line)
+        .ok_or_else(|| Error::parse(1, 1, line, "expected 'Game <id>'"))?;
+    let id = parse_from_capture(Some(game), line, 0)?;
+    let bags = parse_grabs(line)?;
 
     Ok(Game { id, bags })
 }
 
-fn parse_from_capture(c: Option<Captures<'_>>) -> Result<u32> {
+// offset is where the captured text starts within line, for error reporting
+fn parse_from_capture(c: Option<Captures<'_>>, line: &str, offset: usize) -> Result<u32> {
     let mut result = 0;
     if let Some(c) = c {
         let captured = c.get(1);
         if let Some(captured) = captured {
-            result = captured.as_str().parse()?;
+            result = parse_number(captured.as_str(), offset + captured.start() + 1, line)?;
         }
     }
     Ok(result)
 }
 
 fn parse_input(input: &str) -> Result<Vec<Game>> {
-    input.lines().map(parse_game).collect()
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_game(line).map_err(|e| e.on_line(i + 1)))
+        .collect()
 }
 
-fn part1(input: &str) -> Result<u32> {
+fn part1(games: &[Game]) -> u32 {
     let reference = Bag::new(12, 13, 14);
-    let games = parse_input(input)?;
     let possibles = games.iter().filter(|g| g.possible_from(&reference));
-    let result = possibles.map(|g| g.id).sum();
-    Ok(result)
+    possibles.map(|g| g.id).sum()
 }
 
 #[test]
@@ -128,6 +150,29 @@ fn test_parse_game() {
     assert_eq!(g.bags[2].red, 0);
 }
 
+#[test]
+fn test_parse_error_location() {
+    let e = parse_input("Game 1: 3 blue\nGame 2: 1 red; 99999999999 green").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Parse error at line 2, column 16: '99999999999' is not a number: number too large to fit in target type
+2 | Game 2: 1 red; 99999999999 green
+  |                ^"
+    );
+}
+
+#[test]
+fn test_missing_game_id() {
+    assert_eq!(
+        parse_input("Game 1: 3 blue\n\nGame 3: 1 red")
+            .unwrap_err()
+            .to_string(),
+        "Parse error at line 2, column 1: expected 'Game <id>'
+2 |
+  | ^"
+    );
+}
+
 #[cfg(test)]
 static TEST_INPUT: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
 Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
@@ -137,10 +182,10 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
 #[test]
 fn test_part1() {
-    assert_eq!(part1(TEST_INPUT).unwrap(), 8);
+    assert_eq!(part1(&parse_input(TEST_INPUT).unwrap()), 8);
 }
 
 #[test]
 fn test_part2() {
-    assert_eq!(part2(TEST_INPUT).unwrap(), 2286);
+    assert_eq!(part2(&parse_input(TEST_INPUT).unwrap()), 2286);
 }
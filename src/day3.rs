@@ -1,77 +1,209 @@
-use crate::daylib::{DayResult, Parts, Result};
+use crate::{
+    daylib::{Day, Error, Part, Result, Solver},
+    grid::{Grid, Point},
+};
+use itertools::Itertools;
+use std::{collections::BTreeMap, ops::Range};
 
-pub fn day3(input: &str) -> DayResult {
-    let part1 = part1(input)?;
+pub const DAY: Day = Day::new::<Day3>(2023, 3);
 
-    Ok(Parts {
-        part1: Box::new(format!("The sum of the part numbers is {}", part1)),
-        part2: Box::new("Not implemented".to_string()),
-    })
+struct Day3;
+
+impl Solver for Day3 {
+    type Input = Schematic;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_schematic(input)
+    }
+
+    fn part1(schematic: &Self::Input) -> Result<Part> {
+        Ok(Part::described(
+            part1(schematic),
+            "The sum of the part numbers",
+        ))
+    }
+
+    fn part2(schematic: &Self::Input) -> Result<Part> {
+        Ok(Part::described(
+            part2(schematic)?,
+            "The sum of the gear ratios",
+        ))
+    }
+}
+
+// Which characters a schematic may contain and what they mean, so variant puzzles can be read
+#[derive(Debug, Clone)]
+struct Rules {
+    blanks: Vec<char>,
+    symbols: Vec<char>,
+    // Digits are whatever counts as a digit in this base
+    radix: u32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            blanks: vec!['.'],
+            // any ASCII punctuation which isn't a blank, leaving letters and whitespace as mistakes
+            symbols: ('!'..='~')
+                .filter(|c| c.is_ascii_punctuation() && *c != '.')
+                .collect(),
+            radix: 10,
+        }
+    }
+}
+
+impl Rules {
+    fn classify(&self, c: char) -> std::result::Result<Cell, String> {
+        if self.blanks.contains(&c) {
+            Ok(Cell::Blank)
+        } else if let Some(digit) = c.to_digit(self.radix) {
+            Ok(Cell::Digit(digit))
+        } else if self.symbols.contains(&c) {
+            Ok(Cell::Symbol(c))
+        } else {
+            Err(format!("'{}' is not a blank, digit or symbol", c))
+        }
+    }
 }
 
-fn get_part_numbers(input: &str) -> Result<Vec<u32>> {
-    fn is_symbol(c: char) -> bool {
-        c != '.'
+// A symbol which is a gear when it touches exactly this many numbers
+#[derive(Debug, Clone, Copy)]
+struct GearRule {
+    symbol: char,
+    numbers: usize,
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbol: '*',
+            numbers: 2,
+        }
     }
-    let mut extended_lines = input
-        .lines()
-        .map(|line| format!(".{}.", line))
-        .collect::<Vec<String>>();
-
-    let line_length = extended_lines[0].len();
-    extended_lines.insert(0, ".".repeat(line_length));
-    extended_lines.push(".".repeat(line_length));
-
-    enum State {
-        LookingForNumber,
-        ParsingNumber,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Blank,
+    Digit(u32),
+    Symbol(char),
+}
+
+// Every number in the schematic in reading order, so queries don't need to rescan the grid
+#[derive(Debug)]
+struct Schematic {
+    numbers: Vec<Number>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Number {
+    value: u32,
+    row: usize,
+    columns: Range<usize>,
+    // Every symbol touching the number, including diagonally, in reading order
+    symbols: Vec<(Point, char)>,
+}
+
+impl Number {
+    fn is_part(&self) -> bool {
+        !self.symbols.is_empty()
     }
+}
+
+fn parse_schematic(input: &str) -> Result<Schematic> {
+    parse_schematic_with(input, &Rules::default())
+}
+
+fn parse_schematic_with(input: &str, rules: &Rules) -> Result<Schematic> {
+    let grid = Grid::parse_with(input, |c| rules.classify(c))?;
+    let numbers = find_numbers(&grid, input, rules.radix)?;
+    Ok(Schematic { numbers })
+}
+
+fn find_numbers(grid: &Grid<Cell>, input: &str, radix: u32) -> Result<Vec<Number>> {
+    let mut numbers = Vec::new();
+
+    for ((row, cells), line) in grid.rows().enumerate().zip(input.lines()) {
+        let mut column = 0;
+        while column < cells.len() {
+            let Cell::Digit(first) = cells[column] else {
+                column += 1;
+                continue;
+            };
 
-    let mut state = State::LookingForNumber;
-    let mut current_start = 0;
-
-    let mut part_numbers = Vec::new();
-
-    for (line_number, line) in extended_lines.iter().enumerate() {
-        for (char_number, c) in line.chars().enumerate() {
-            match state {
-                State::LookingForNumber => {
-                    if c.is_ascii_digit() {
-                        state = State::ParsingNumber;
-                        current_start = char_number;
-                    }
-                }
-                State::ParsingNumber => {
-                    if !c.is_ascii_digit() {
-                        // got the end of the number
-                        state = State::LookingForNumber;
-
-                        let current_number = line[current_start..char_number].parse::<u32>()?;
-
-                        let left = line.chars().nth(current_start - 1).unwrap() != '.';
-                        let right = c != '.';
-                        let above = extended_lines[line_number - 1]
-                            [current_start - 1..=char_number]
-                            .chars()
-                            .any(is_symbol);
-                        let below = extended_lines[line_number + 1]
-                            [current_start - 1..=char_number]
-                            .chars()
-                            .any(is_symbol);
-
-                        if left || right || above || below {
-                            part_numbers.push(current_number);
-                        }
-                    }
-                }
+            let start = column;
+            let mut value = Some(first);
+            column += 1;
+            while let Some(&Cell::Digit(digit)) = cells.get(column) {
+                value = value
+                    .and_then(|v| v.checked_mul(radix))
+                    .and_then(|v| v.checked_add(digit));
+                column += 1;
             }
+
+            let value = value.ok_or_else(|| {
+                let offset = line.char_indices().nth(start).map_or(0, |(i, _)| i);
+                Error::parse(row + 1, offset + 1, line, "the number is too large")
+            })?;
+
+            let symbols = (start..column)
+                .flat_map(|column| grid.neighbours8(Point::new(row, column)))
+                .filter_map(|p| match grid[p] {
+                    Cell::Symbol(c) => Some((p, c)),
+                    _ => None,
+                })
+                .sorted()
+                .dedup()
+                .collect();
+
+            numbers.push(Number {
+                value,
+                row,
+                columns: start..column,
+                symbols,
+            });
         }
+    }
+
+    Ok(numbers)
+}
+
+fn get_part_numbers(schematic: &Schematic) -> Vec<u32> {
+    schematic
+        .numbers
+        .iter()
+        .filter(|n| n.is_part())
+        .map(|n| n.value)
+        .collect()
+}
 
-        // numbers can't wrap
-        state = State::LookingForNumber;
+// A gear's ratio is the product of the numbers it touches
+fn get_gear_ratios(schematic: &Schematic, rule: GearRule) -> Result<Vec<u64>> {
+    let mut touching: BTreeMap<Point, Vec<u32>> = BTreeMap::new();
+    for number in &schematic.numbers {
+        for &(p, c) in &number.symbols {
+            if c == rule.symbol {
+                touching.entry(p).or_default().push(number.value);
+            }
+        }
     }
 
-    Ok(part_numbers)
+    touching
+        .into_iter()
+        .filter(|(_, values)| values.len() == rule.numbers)
+        .map(|(p, values)| {
+            values
+                .into_iter()
+                .try_fold(1u64, |ratio, v| ratio.checked_mul(v.into()))
+                .ok_or_else(|| format!("The ratio of the gear at {} is too large", p).into())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn schematic(input: &str) -> Schematic {
+    parse_schematic(input).unwrap()
 }
 
 #[cfg(test)]
@@ -89,101 +221,244 @@ static TEST_INPUT: &str = "467..114..
 #[test]
 fn test_parse() {
     assert_eq!(
-        get_part_numbers(TEST_INPUT).unwrap(),
+        get_part_numbers(&schematic(TEST_INPUT)),
         vec![467, 35, 633, 617, 592, 755, 664, 598]
     );
 }
 
 #[test]
 fn test_parse_right_edges() {
-    assert_eq!(get_part_numbers("...2").unwrap(), vec![]);
-    assert_eq!(get_part_numbers("..*2").unwrap(), vec![2]);
-    assert_eq!(get_part_numbers("..%.\n...2").unwrap(), vec![2]);
-    assert_eq!(get_part_numbers("...2\n...*").unwrap(), vec![2]);
-    assert_eq!(get_part_numbers("...2\n....").unwrap(), vec![]);
+    assert_eq!(get_part_numbers(&schematic("...2")), vec![]);
+    assert_eq!(get_part_numbers(&schematic("..*2")), vec![2]);
+    assert_eq!(get_part_numbers(&schematic("..%.\n...2")), vec![2]);
+    assert_eq!(get_part_numbers(&schematic("...2\n...*")), vec![2]);
+    assert_eq!(get_part_numbers(&schematic("...2\n....")), vec![]);
 }
 
 #[test]
 fn test_parse_right() {
-    assert_eq!(get_part_numbers(".2.").unwrap(), vec![]);
-    assert_eq!(get_part_numbers(".2*").unwrap(), vec![2]);
+    assert_eq!(get_part_numbers(&schematic(".2.")), vec![]);
+    assert_eq!(get_part_numbers(&schematic(".2*")), vec![2]);
 }
 
 #[test]
 fn test_parse_left() {
-    assert_eq!(get_part_numbers("*2.").unwrap(), vec![2]);
+    assert_eq!(get_part_numbers(&schematic("*2.")), vec![2]);
 }
 
 #[test]
 fn test_parse_topleft() {
-    assert_eq!(get_part_numbers("*..\n.2.").unwrap(), vec![2]);
+    assert_eq!(get_part_numbers(&schematic("*..\n.2.")), vec![2]);
 }
 
 #[test]
 fn test_parse_topright() {
-    assert_eq!(get_part_numbers("..*\n.2.").unwrap(), vec![2]);
+    assert_eq!(get_part_numbers(&schematic("..*\n.2.")), vec![2]);
 }
 
 #[test]
 fn test_parse_botleft() {
-    assert_eq!(get_part_numbers(".2.\n*..").unwrap(), vec![2]);
+    assert_eq!(get_part_numbers(&schematic(".2.\n*..")), vec![2]);
 }
 
 #[test]
 fn test_parse_botright() {
-    assert_eq!(get_part_numbers(".2.\n..*").unwrap(), vec![2]);
+    assert_eq!(get_part_numbers(&schematic(".2.\n..*")), vec![2]);
 }
 
-fn part1(input: &str) -> Result<u32> {
-    Ok(get_part_numbers(input)?.into_iter().sum())
+#[test]
+fn test_bad_schematics() {
+    let error = |input: &str| parse_schematic(input).unwrap_err().to_string();
+    assert_eq!(
+        error(""),
+        "Parse error at line 1, column 1: the grid is empty
+1 |
+  | ^"
+    );
+    assert_eq!(
+        error("467..\n...*\n..35."),
+        "Parse error at line 2, column 5: expected 5 columns but found 4
+2 | ...*
+  |     ^"
+    );
+    assert_eq!(
+        error("467..\n...*..\n..35."),
+        "Parse error at line 2, column 6: expected 5 columns but found 6
+2 | ...*..
+  |      ^"
+    );
+    assert_eq!(
+        error("467..\n..é..\n..35."),
+        "Parse error at line 2, column 3: 'é' is not a blank, digit or symbol
+2 | ..é..
+  |   ^"
+    );
 }
 
 #[test]
-fn test_part1() {
-    assert_eq!(part1(TEST_INPUT).unwrap(), 4361);
+fn test_invalid_characters() {
+    let error = |input: &str| parse_schematic(input).unwrap_err().to_string();
+    assert_eq!(
+        error("467..\n...a.\n..35."),
+        "Parse error at line 2, column 4: 'a' is not a blank, digit or symbol
+2 | ...a.
+  |    ^"
+    );
+    assert_eq!(
+        error("467..\n... .\n..35."),
+        "Parse error at line 2, column 4: ' ' is not a blank, digit or symbol
+2 | ... .
+  |    ^"
+    );
+    assert_eq!(
+        error("..99999999999*"),
+        "Parse error at line 1, column 3: the number is too large
+1 | ..99999999999*
+  |   ^"
+    );
 }
 
-#[allow(unused)]
-fn get_gear_ratios(input: &str) -> Result<Vec<u32>> {
-    fn might_be_gear(c: char) -> bool {
-        c == '*'
-    }
+#[test]
+fn test_any_punctuation_is_a_symbol() {
+    let schematic = schematic("1....\n.!...\n..2~.\n4.._.\n...(5");
+    assert_eq!(get_part_numbers(&schematic), vec![1, 2, 5]);
+}
+
+#[test]
+fn test_custom_rules() {
+    let rules = Rules {
+        blanks: vec![' ', '.'],
+        symbols: vec!['?', '!'],
+        radix: 16,
+    };
+    let schematic = parse_schematic_with("ff ?\n..a.\n1!..", &rules).unwrap();
+    assert_eq!(get_part_numbers(&schematic), vec![10, 1]);
+    assert_eq!(
+        get_gear_ratios(
+            &schematic,
+            GearRule {
+                symbol: '?',
+                numbers: 2
+            }
+        )
+        .unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        get_gear_ratios(
+            &schematic,
+            GearRule {
+                symbol: '!',
+                numbers: 2
+            }
+        )
+        .unwrap(),
+        vec![10]
+    );
+    assert!(parse_schematic_with("1*2", &rules).is_err());
+}
 
-    let mut extended_lines = input
-        .lines()
-        .map(|line| format!(".{}.", line))
-        .collect::<Vec<String>>();
-
-    let line_length = extended_lines[0].len();
-    extended_lines.insert(0, ".".repeat(line_length));
-    extended_lines.push(".".repeat(line_length));
-
-    let mut gear_ratios = Vec::new();
-
-    for (line_number, line) in extended_lines.iter().enumerate() {
-        for (char_number, c) in line.chars().enumerate() {
-            if c == '*' {
-                // let's look for two connected numbers
-                let left_num = line
-                    .chars()
-                    .rev()
-                    .skip(line.len() - char_number)
-                    .take_while(char::is_ascii_digit)
-                    .collect::<String>()
-                    .parse::<u32>();
-                let right_num = line
-                    .chars()
-                    .skip(char_number + 1)
-                    .take_while(char::is_ascii_digit)
-                    .collect::<String>()
-                    .parse::<u32>();
+#[test]
+fn test_gear_rules() {
+    let input = "1.2\n.*.\n.3.";
+    let gear = |numbers| {
+        get_gear_ratios(
+            &schematic(input),
+            GearRule {
+                symbol: '*',
+                numbers,
+            },
+        )
+    };
+    assert_eq!(gear(2).unwrap(), vec![]);
+    assert_eq!(gear(3).unwrap(), vec![6]);
+    assert_eq!(
+        get_gear_ratios(
+            &schematic("4000000000*4000000000\n......4000000000....."),
+            GearRule {
+                symbol: '*',
+                numbers: 3
             }
+        )
+        .unwrap_err()
+        .to_string(),
+        "The ratio of the gear at (0, 10) is too large"
+    );
+}
+
+fn part1(schematic: &Schematic) -> u32 {
+    get_part_numbers(schematic).into_iter().sum()
+}
+
+#[test]
+fn test_numbers() {
+    let example = schematic(TEST_INPUT);
+    assert_eq!(example.numbers.len(), 10);
+    assert_eq!(
+        example.numbers[0],
+        Number {
+            value: 467,
+            row: 0,
+            columns: 0..3,
+            symbols: vec![(Point::new(1, 3), '*')],
         }
-    }
+    );
+    assert_eq!(
+        example.numbers[1],
+        Number {
+            value: 114,
+            row: 0,
+            columns: 5..8,
+            symbols: vec![],
+        }
+    );
+    assert_eq!(example.numbers[6].symbols, vec![(Point::new(5, 5), '+')]);
+
+    // each symbol is listed once, however many digits it touches
+    assert_eq!(
+        schematic("12*\n$..").numbers[0].symbols,
+        vec![(Point::new(0, 2), '*'), (Point::new(1, 0), '$')]
+    );
+}
+
+#[test]
+fn test_part1() {
+    assert_eq!(part1(&schematic(TEST_INPUT)), 4361);
+}
 
-    Ok(gear_ratios)
+fn part2(schematic: &Schematic) -> Result<u64> {
+    Ok(get_gear_ratios(schematic, GearRule::default())?
+        .into_iter()
+        .sum())
 }
 
-fn find_number_touching(line: &str, touch_index: usize) -> Result<u32> {
-    todo!();
+#[cfg(test)]
+fn gear_ratios(input: &str) -> Vec<u64> {
+    get_gear_ratios(&schematic(input), GearRule::default()).unwrap()
+}
+
+#[test]
+fn test_gear_ratios() {
+    assert_eq!(gear_ratios(TEST_INPUT), vec![16345, 451490]);
+}
+
+#[test]
+fn test_gear_ratios_diagonals() {
+    assert_eq!(gear_ratios("12.34\n..*.."), vec![408]);
+    assert_eq!(gear_ratios("..*..\n12.34"), vec![408]);
+    assert_eq!(gear_ratios("12...\n..*..\n...34"), vec![408]);
+    assert_eq!(gear_ratios("2*3"), vec![6]);
+}
+
+#[test]
+fn test_gear_ratios_need_exactly_two() {
+    assert_eq!(gear_ratios("12*.."), vec![]);
+    assert_eq!(gear_ratios("1.2\n.*.\n.3."), vec![]);
+    // a long number touching the gear with several digits still counts once
+    assert_eq!(gear_ratios("123.\n.*..\n.4.."), vec![492]);
+}
+
+#[test]
+fn test_part2() {
+    assert_eq!(part2(&schematic(TEST_INPUT)).unwrap(), 467835);
 }
@@ -1,66 +1,115 @@
+use itertools::Itertools;
 use std::{
     collections::{HashMap, HashSet},
-    error::Error,
+    ops::Range,
     str::FromStr,
 };
 
-use crate::daylib::{self, DayResult, Parts};
+use crate::{
+    daylib::{self, parse_number, words, Day, Error, Format, Part, Solver},
+    debug,
+    json::Json,
+    runner::table,
+    trace,
+};
 
-pub fn day4(input: &str) -> DayResult {
-    let part1 = part1(input)?;
-    let part2 = part2(input)?;
+pub const DAY: Day = Day::new::<Day4>(2023, 4)
+    .with_check(check_cards)
+    .with_report(report);
 
-    Ok(Parts {
-        part1: Box::new(format!("The pile of cards is worth {} points", part1)),
-        part2: Box::new(format!("The pile of cards now has {} in it", part2)),
-    })
+struct Day4;
+
+impl Solver for Day4 {
+    type Input = Vec<Card>;
+
+    fn parse(input: &str) -> daylib::Result<Self::Input> {
+        parse_cards(input)
+    }
+
+    fn part1(cards: &Self::Input) -> daylib::Result<Part> {
+        Ok(Part::described(
+            part1(cards)?,
+            "Points the pile of cards is worth",
+        ))
+    }
+
+    fn part2(cards: &Self::Input) -> daylib::Result<Part> {
+        Ok(Part::described(part2(cards)?, "Cards in the pile"))
+    }
 }
 
+#[derive(Debug)]
 struct Card {
-    card_number: u8,
-    winning_numbers: HashSet<u8>,
-    numbers: HashSet<u8>,
+    card_number: u64,
+    winning_numbers: HashSet<u64>,
+    numbers: HashSet<u64>,
 }
 
 impl Card {
-    fn score(&self) -> u32 {
-        let winning_numbers_i_have_count = self.winning_numbers.intersection(&self.numbers).count();
-        match winning_numbers_i_have_count {
-            0 => 0,
-            1 => 1,
-            _ => 2_u32.pow(
-                (winning_numbers_i_have_count - 1)
-                    .try_into()
-                    .expect("Winning number count doesn't fit in a u32"),
-            ),
-        }
+    fn matches(&self) -> usize {
+        self.winning_numbers.intersection(&self.numbers).count()
     }
 
-    fn duplicates(&self) -> impl Iterator<Item = u8> {
-        let winning_numbers_i_have_count = self.winning_numbers.intersection(&self.numbers).count();
+    fn matching_numbers(&self) -> Vec<u64> {
+        let mut matching = self
+            .winning_numbers
+            .intersection(&self.numbers)
+            .copied()
+            .collect::<Vec<u64>>();
+        matching.sort();
+        matching
+    }
 
-        self.card_number + 1..=self.card_number + winning_numbers_i_have_count as u8
+    // Doubles for every match after the first
+    fn score(&self) -> daylib::Result<u64> {
+        match self.matches() {
+            0 => Ok(0),
+            matches => u32::try_from(matches - 1)
+                .ok()
+                .and_then(|doublings| 1u64.checked_shl(doublings))
+                .ok_or_else(|| {
+                    format!("The score of card {} is too large", self.card_number).into()
+                }),
+        }
+    }
+
+    // The numbers of the cards this one wins a copy of
+    fn duplicates(&self) -> Range<u64> {
+        let start = self.card_number.saturating_add(1);
+        start..start.saturating_add(self.matches() as u64)
     }
 }
 
 impl FromStr for Card {
-    type Err = Box<dyn Error>;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let colon = s.find(':').ok_or("No colon")?;
-        let pipe = s.find('|').ok_or("No pipe")?;
-        let space = s.find(' ').ok_or("No space")?;
-        let winning_numbers_str = s[colon + 1..pipe].trim();
-        let have_numbers_str = s[pipe + 1..].trim();
-        let winning_numbers = winning_numbers_str
-            .split_ascii_whitespace()
-            .map(u8::from_str)
-            .collect::<Result<HashSet<u8>, _>>()?;
-        let numbers = have_numbers_str
-            .split_ascii_whitespace()
-            .map(u8::from_str)
-            .collect::<Result<HashSet<u8>, _>>()?;
-        let card_number = u8::from_str(s[space + 1..colon].trim())?;
+    type Err = Error;
+
+    fn from_str(s: &str) -> daylib::Result<Self> {
+        let colon = s
+            .find(':')
+            .ok_or_else(|| Error::parse(1, s.len() + 1, s, "no colon"))?;
+        let pipe = s
+            .find('|')
+            .ok_or_else(|| Error::parse(1, s.len() + 1, s, "no pipe"))?;
+        if pipe < colon {
+            return Err(Error::parse(1, pipe + 1, s, "pipe before colon"));
+        }
+        let space = s[..colon]
+            .find(' ')
+            .ok_or_else(|| Error::parse(1, colon + 1, s, "no space before colon"))?;
+
+        // start is the byte offset of the section within the line
+        let parse_numbers = |start: usize, end: usize| {
+            words(&s[start..end])
+                .map(|(column, word)| parse_number::<u64>(word, start + column, s))
+                .collect::<daylib::Result<HashSet<u64>>>()
+        };
+        let winning_numbers = parse_numbers(colon + 1, pipe)?;
+        let numbers = parse_numbers(pipe + 1, s.len())?;
+
+        let (column, card_number) = words(&s[space + 1..colon])
+            .next()
+            .ok_or_else(|| Error::parse(1, colon + 1, s, "no card number"))?;
+        let card_number = parse_number(card_number, space + 1 + column, s)?;
 
         Ok(Card {
             card_number,
@@ -70,37 +119,207 @@ impl FromStr for Card {
     }
 }
 
-fn part1(input: &str) -> daylib::Result<u32> {
-    Ok(input
-        .lines()
-        .map(Card::from_str)
-        .collect::<Result<Vec<Card>, _>>()?
-        .iter()
-        .map(Card::score)
-        .sum())
+// Cards must be numbered one after another, since each card wins copies of the ones after it
+fn parse_cards(input: &str) -> daylib::Result<Vec<Card>> {
+    let mut seen = HashMap::new();
+    let mut cards: Vec<Card> = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let card = Card::from_str(line).map_err(|e| e.on_line(i + 1))?;
+        check_numbering(&card, cards.last(), &mut seen, i + 1, line)?;
+        cards.push(card);
+    }
+
+    Ok(cards)
 }
 
-fn part2(input: &str) -> daylib::Result<usize> {
-    let cards = input
-        .lines()
-        .map(Card::from_str)
-        .collect::<Result<Vec<Card>, _>>()?
-        .into_iter()
-        .map(|card| (card.card_number, card))
-        .collect::<HashMap<u8, Card>>();
+// seen holds the line each card number was first found on
+fn check_numbering(
+    card: &Card,
+    previous: Option<&Card>,
+    seen: &mut HashMap<u64, usize>,
+    line_number: usize,
+    line: &str,
+) -> daylib::Result<()> {
+    let column = words(line).nth(1).map_or(1, |(column, _)| column);
 
-    let mut unevaluated_cards = cards.keys().cloned().collect::<Vec<u8>>();
+    if let Some(&first_line) = seen.get(&card.card_number) {
+        return Err(Error::parse(
+            line_number,
+            column,
+            line,
+            format!(
+                "card {} was already seen on line {}",
+                card.card_number, first_line
+            ),
+        ));
+    }
+    seen.insert(card.card_number, line_number);
+
+    if let Some(previous) = previous {
+        if previous.card_number.checked_add(1) != Some(card.card_number) {
+            return Err(Error::parse(
+                line_number,
+                column,
+                line,
+                format!(
+                    "expected card {} after card {}",
+                    previous.card_number as u128 + 1,
+                    previous.card_number
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Everything parse_cards rejects, plus repeated numbers within a list and lists which are a
+// different length to the first card's. Carries on past problems so they're all reported.
+fn check_cards(input: &str) -> Vec<Error> {
+    let mut problems = Vec::new();
+    let mut seen = HashMap::new();
+    let mut previous: Option<Card> = None;
+    // The list lengths of the first card, and the line it was on
+    let mut expected_lengths = None;
+
+    for (i, line) in input.lines().enumerate() {
+        let card = match Card::from_str(line) {
+            Ok(card) => card,
+            Err(e) => {
+                problems.push(e.on_line(i + 1));
+                continue;
+            }
+        };
+        if let Err(e) = check_numbering(&card, previous.as_ref(), &mut seen, i + 1, line) {
+            problems.push(e);
+        }
+
+        // from_str has already checked these are here
+        let colon = line.find(':').unwrap_or(0);
+        let pipe = line.find('|').unwrap_or(line.len());
+        let lists = [
+            ("winning numbers", colon + 1, pipe),
+            ("numbers you have", pipe + 1, line.len()),
+        ];
 
-    let mut evaluated_cards = Vec::new();
+        let mut lengths = [0; 2];
+        for (length, &(name, start, end)) in lengths.iter_mut().zip(&lists) {
+            let mut numbers = HashSet::new();
+            for (column, word) in words(&line[start..end]) {
+                *length += 1;
+                if !numbers.insert(word.trim_start_matches('0')) {
+                    problems.push(Error::parse(
+                        i + 1,
+                        start + column,
+                        line,
+                        format!("{} appears more than once in the {}", word, name),
+                    ));
+                }
+            }
+        }
 
-    while let Some(current) = unevaluated_cards.pop() {
-        let this_card = &cards[&current];
-        let new_cards = this_card.duplicates();
-        unevaluated_cards.extend(new_cards);
-        evaluated_cards.push(this_card);
+        let (expected, first_line) = *expected_lengths.get_or_insert((lengths, i + 1));
+        for ((length, expected), &(name, _, end)) in lengths.iter().zip(expected).zip(&lists) {
+            if *length != expected {
+                problems.push(Error::parse(
+                    i + 1,
+                    end + 1,
+                    line,
+                    format!(
+                        "expected {} {} like the card on line {} but found {}",
+                        expected, name, first_line, length
+                    ),
+                ));
+            }
+        }
+
+        previous = Some(card);
     }
 
-    Ok(evaluated_cards.len())
+    problems
+}
+
+fn part1(cards: &[Card]) -> daylib::Result<u64> {
+    cards.iter().try_fold(0u64, |total, card| {
+        total
+            .checked_add(card.score()?)
+            .ok_or_else(|| "The pile of cards is worth too many points to count".into())
+    })
+}
+
+// Each card wins one copy of the next few cards for every copy of itself, and wins only
+// affect later cards, so one pass in order is enough to count them all
+fn copies(cards: &[Card]) -> daylib::Result<Vec<u64>> {
+    let first = cards.first().map_or(0, |card| card.card_number);
+    let mut copies = vec![1u64; cards.len()];
+    for (i, card) in cards.iter().enumerate() {
+        let won = card
+            .duplicates()
+            .map(|number| (number - first) as usize)
+            .take_while(|&j| j < cards.len());
+        for j in won {
+            copies[j] = copies[j]
+                .checked_add(copies[i])
+                .ok_or("Too many copies of the cards to count")?;
+        }
+        trace!("card {} has {} copies", card.card_number, copies[i]);
+    }
+
+    Ok(copies)
+}
+
+fn part2(cards: &[Card]) -> daylib::Result<u64> {
+    let total = copies(cards)?
+        .into_iter()
+        .try_fold(0u64, u64::checked_add)
+        .ok_or("Too many cards in the pile to count")?;
+    debug!("{} cards end up in the pile", total);
+    Ok(total)
+}
+
+// What each card matched, what it scored and how many copies of it end up in the pile
+fn report(input: &str, format: Format) -> daylib::Result<String> {
+    let cards = parse_cards(input)?;
+    let copies = copies(&cards)?;
+
+    Ok(match format {
+        Format::Text => {
+            let mut rows = vec![["Card", "Matching", "Score", "Copies"].map(String::from)];
+            for (card, copies) in cards.iter().zip(copies) {
+                rows.push([
+                    card.card_number.to_string(),
+                    card.matching_numbers().iter().join(" "),
+                    card.score()?.to_string(),
+                    copies.to_string(),
+                ]);
+            }
+            table(&rows)
+        }
+        Format::Json => Json::Array(
+            cards
+                .iter()
+                .zip(copies)
+                .map(|(card, copies)| {
+                    Ok(Json::object([
+                        ("card", Json::number(card.card_number)),
+                        (
+                            "matching",
+                            Json::Array(
+                                card.matching_numbers()
+                                    .into_iter()
+                                    .map(Json::number)
+                                    .collect(),
+                            ),
+                        ),
+                        ("score", Json::number(card.score()?)),
+                        ("copies", Json::number(copies)),
+                    ]))
+                })
+                .collect::<daylib::Result<_>>()?,
+        )
+        .to_string(),
+    })
 }
 
 #[test]
@@ -111,7 +330,34 @@ fn test_card_score() {
         numbers: vec![83, 86, 6, 31, 17, 9, 48, 53].into_iter().collect(),
     };
 
-    assert_eq!(card.score(), 8);
+    assert_eq!(card.score().unwrap(), 8);
+}
+
+#[test]
+fn test_part1_huge_scores() {
+    let numbers = (1..=40).join(" ");
+    let input = format!("Card 1: {} | {}", numbers, numbers);
+    let cards = parse_cards(&input).unwrap();
+    assert_eq!(part1(&cards).unwrap(), 1 << 39);
+
+    let numbers = (1..=65).join(" ");
+    let input = format!("Card 1: {} | {}", numbers, numbers);
+    let cards = parse_cards(&input).unwrap();
+    assert_eq!(
+        part1(&cards).unwrap_err().to_string(),
+        "The score of card 1 is too large"
+    );
+
+    let numbers = (1..=64).join(" ");
+    let input = format!(
+        "Card 1: {} | {}\nCard 2: {} | {}",
+        numbers, numbers, numbers, numbers
+    );
+    let cards = parse_cards(&input).unwrap();
+    assert_eq!(
+        part1(&cards).unwrap_err().to_string(),
+        "The pile of cards is worth too many points to count"
+    );
 }
 
 #[test]
@@ -123,6 +369,35 @@ fn test_parse_card() {
     assert_eq!(card.card_number, 3);
 }
 
+#[test]
+fn test_parse_card_errors() {
+    let error = |input: &str| parse_cards(input).unwrap_err().to_string();
+    assert_eq!(
+        error("Card 1: 41 48 | 83 86\nCard 2: 13 32 | 61 99999999999999999999"),
+        "Parse error at line 2, column 20: '99999999999999999999' is not a number: number too large to fit in target type
+2 | Card 2: 13 32 | 61 99999999999999999999
+  |                    ^"
+    );
+    assert_eq!(
+        error("Card 1 41 48 | 83 86"),
+        "Parse error at line 1, column 21: no colon
+1 | Card 1 41 48 | 83 86
+  |                     ^"
+    );
+    assert_eq!(
+        error("é 1 |x"),
+        "Parse error at line 1, column 7: no colon
+1 | é 1 |x
+  |       ^"
+    );
+    assert_eq!(
+        error("Card x: 41 48 | 83 86"),
+        "Parse error at line 1, column 6: 'x' is not a number: invalid digit found in string
+1 | Card x: 41 48 | 83 86
+  |      ^"
+    );
+}
+
 #[test]
 fn test_part1() {
     let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
@@ -132,7 +407,7 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    let result = part1(input).unwrap();
+    let result = part1(&parse_cards(input).unwrap()).unwrap();
 
     assert_eq!(result, 13);
 }
@@ -141,9 +416,7 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 fn test_duplicates() {
     let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53";
     let card = Card::from_str(input).unwrap();
-    let dups = card.duplicates().collect::<Vec<u8>>();
-
-    assert_eq!(dups, vec![2, 3, 4, 5])
+    assert_eq!(card.duplicates(), 2..6);
 }
 
 #[test]
@@ -155,7 +428,138 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    let result = part2(input).unwrap();
+    let result = part2(&parse_cards(input).unwrap()).unwrap();
 
     assert_eq!(result, 30);
 }
+
+#[test]
+fn test_big_cards() {
+    let cards =
+        parse_cards("Card 300: 1000 2 | 1000 3\nCard 301: 70000 | 70000\nCard 302: 1 | 2").unwrap();
+    assert_eq!(cards[0].duplicates(), 301..302);
+    assert_eq!(part1(&cards).unwrap(), 2);
+    assert_eq!(part2(&cards).unwrap(), 6);
+
+    let cards = parse_cards(&doubling_cards(300)).unwrap();
+    assert_eq!(cards[298].duplicates(), 300..301);
+    assert_eq!(
+        part2(&cards).unwrap_err().to_string(),
+        "Too many copies of the cards to count"
+    );
+
+    let last = Card::from_str("Card 18446744073709551615: 1 | 1").unwrap();
+    assert_eq!(last.duplicates(), u64::MAX..u64::MAX);
+}
+
+#[test]
+fn test_card_numbering_errors() {
+    let error = |input: &str| parse_cards(input).unwrap_err().to_string();
+    assert_eq!(
+        error("Card 1: 41 48 | 83 86\nCard 2: 13 32 | 61 30\nCard 1: 1 | 2"),
+        "Parse error at line 3, column 6: card 1 was already seen on line 1
+3 | Card 1: 1 | 2
+  |      ^"
+    );
+    assert_eq!(
+        error("Card 1: 41 48 | 83 86\nCard   3: 13 32 | 61 30"),
+        "Parse error at line 2, column 8: expected card 2 after card 1
+2 | Card   3: 13 32 | 61 30
+  |        ^"
+    );
+}
+
+// Every card matches all the cards after it, so card n ends up with 2^(n-1) copies
+#[cfg(test)]
+fn doubling_cards(count: u64) -> String {
+    (1..=count)
+        .map(|n| {
+            let numbers = (n + 1..=count).map(|m| m.to_string()).join(" ");
+            format!("Card {}: {} | {}", n, numbers, numbers)
+        })
+        .join("\n")
+}
+
+#[test]
+fn test_part2_huge_copy_counts() {
+    let cards = parse_cards(&doubling_cards(63)).unwrap();
+    assert_eq!(part2(&cards).unwrap(), (1 << 63) - 1);
+
+    let cards = parse_cards(&doubling_cards(65)).unwrap();
+    assert_eq!(
+        part2(&cards).unwrap_err().to_string(),
+        "Too many copies of the cards to count"
+    );
+}
+
+#[test]
+fn test_check_cards() {
+    let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1";
+    assert!(check_cards(input).is_empty());
+
+    let input = "Card 1: 41 48 83 | 83 86  6 31
+Card 2: 13 32 13 | 61 30 68 61
+Card 4:  1 21 | 69 82 63 72 16
+Card 4:  1 21 53 | x";
+    let problems = check_cards(input)
+        .into_iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        vec![
+            "Parse error at line 2, column 15: 13 appears more than once in the winning numbers
+2 | Card 2: 13 32 13 | 61 30 68 61
+  |               ^",
+            "Parse error at line 2, column 29: 61 appears more than once in the numbers you have
+2 | Card 2: 13 32 13 | 61 30 68 61
+  |                             ^",
+            "Parse error at line 3, column 6: expected card 3 after card 2
+3 | Card 4:  1 21 | 69 82 63 72 16
+  |      ^",
+            "Parse error at line 3, column 15: expected 3 winning numbers like the card on line 1 but found 2
+3 | Card 4:  1 21 | 69 82 63 72 16
+  |               ^",
+            "Parse error at line 3, column 31: expected 4 numbers you have like the card on line 1 but found 5
+3 | Card 4:  1 21 | 69 82 63 72 16
+  |                               ^",
+            "Parse error at line 4, column 20: 'x' is not a number: invalid digit found in string
+4 | Card 4:  1 21 53 | x
+  |                    ^",
+        ]
+    );
+}
+
+#[test]
+fn test_check_duplicate_card() {
+    let problems = check_cards("Card 1: 1 | 2\nCard 2: 3 | 4\nCard 1: 5 | 6");
+    assert_eq!(problems.len(), 1);
+    assert_eq!(
+        problems[0].to_string(),
+        "Parse error at line 3, column 6: card 1 was already seen on line 1
+3 | Card 1: 5 | 6
+  |      ^"
+    );
+}
+
+#[cfg(test)]
+static TEST_INPUT: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1";
+
+#[test]
+fn test_report() {
+    assert_eq!(
+        report(TEST_INPUT, Format::Text).unwrap(),
+        "Card | Matching    | Score | Copies
+1    | 17 48 83 86 | 8     | 1
+2    | 32 61       | 2     | 2
+3    | 1 21        | 2     | 4"
+    );
+    assert_eq!(
+        report(TEST_INPUT, Format::Json).unwrap(),
+        r#"[{"card":1,"matching":[17,48,83,86],"score":8,"copies":1},{"card":2,"matching":[32,61],"score":2,"copies":2},{"card":3,"matching":[1,21],"score":2,"copies":4}]"#
+    );
+}
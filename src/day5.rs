@@ -1,43 +1,337 @@
-use crate::daylib::{DayResult, Parts, Result};
-use std::{collections::HashSet, ops::Range, str::FromStr};
+use crate::daylib::{parse_number, words, Day, Error, Format, Part, Result, Solver};
+use crate::interval::IntervalSet;
+use crate::json::Json;
+use crate::{debug, trace};
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+};
 
-pub fn day5(input: &str) -> DayResult {
-    let r = part1(input)?;
-    let r2 = part2(input)?;
+pub const DAY: Day = Day::new::<Day5>(2023, 5)
+    .with_check(check_almanac)
+    .with_report(report);
 
-    Ok(Parts {
-        part1: Box::new(format!("The smallest location is {}", r)),
-        part2: Box::new(format!("The smallest expanded location is {}", r2)),
-    })
+struct Day5;
+
+impl Solver for Day5 {
+    type Input = (Vec<u64>, WeirdMaps);
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1((seeds, weirdmaps): &Self::Input) -> Result<Part> {
+        Ok(Part::described(
+            part1(seeds, weirdmaps)?,
+            "The smallest location",
+        ))
+    }
+
+    fn part2((seeds, weirdmaps): &Self::Input) -> Result<Part> {
+        Ok(Part::described(
+            part2(seeds, weirdmaps)?,
+            "The smallest expanded location",
+        ))
+    }
 }
 
 #[derive(Clone, Debug)]
 struct WeirdMap {
+    // Sorted, not overlapping and covering every u64, with rules which leave values where they
+    // are filling the gaps, so the rule for any value can be found by binary search
     ranges: Vec<MappedRange>,
 }
 
 impl WeirdMap {
-    fn new(ranges: Vec<MappedRange>) -> Self {
+    // Where rules overlap the first one, in order of where they start, wins without complaint.
+    // Only --strict reports overlaps, through check_almanac. Empty rules are dropped.
+    fn new(mut rules: Vec<MappedRange>) -> Self {
+        rules.retain(|r| r.source_start < r.source_end);
+        rules.sort_by_key(|r| r.source_start);
+
+        let mut ranges = Vec::with_capacity(rules.len() * 2 + 1);
+        let mut start = 0;
+        for rule in rules {
+            if rule.source_end <= start {
+                continue;
+            }
+            if start < rule.source_start {
+                ranges.push(MappedRange::identity(start..rule.source_start));
+                start = rule.source_start;
+            }
+            ranges.push(MappedRange {
+                source_start: start,
+                dest_start: (start as i128 + rule.offset()) as u64,
+                source_end: rule.source_end,
+            });
+            start = rule.source_end;
+        }
+        if start < u64::MAX {
+            ranges.push(MappedRange::identity(start..u64::MAX));
+        }
+
         Self { ranges }
     }
 
-    fn map(&self, source: Range<u64>) -> HashSet<Range<u64>> {
-        let set: HashSet<Range<u64>> = self
+    // The rules which move values, leaving out the gaps
+    fn rules(&self) -> impl Iterator<Item = &MappedRange> {
+        self.ranges.iter().filter(|r| r.offset() != 0)
+    }
+
+    // The rules covering any of source, in order
+    fn covering(&self, source: Range<u64>) -> impl Iterator<Item = &MappedRange> {
+        let first = self
             .ranges
+            .partition_point(|r| r.source_end <= source.start);
+        self.ranges[first..]
             .iter()
-            .filter_map(|r| r.map(source.clone()))
-            .flatten()
-            .collect();
+            .take_while(move |r| r.source_start < source.end)
+    }
 
-        if set.is_empty() {
-            HashSet::from([source])
-        } else {
-            set
+    fn lookup(&self, value: u64) -> u64 {
+        let i = self.ranges.partition_point(|r| r.source_end <= value);
+        self.ranges
+            .get(i)
+            .and_then(|rule| rule.map_value(value))
+            .unwrap_or(value)
+    }
+
+    fn map(&self, source: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let mut mapped = IntervalSet::new();
+        for range in source.ranges() {
+            mapped.extend(
+                self.covering(range.clone())
+                    .filter_map(|rule| rule.map(range.clone())),
+            );
+        }
+        mapped
+    }
+
+    // Each rule as the values it covers and how far it moves them
+    fn pieces(&self) -> impl Iterator<Item = (Range<u64>, i128)> + '_ {
+        self.ranges
+            .iter()
+            .map(|r| (r.source_start..r.source_end, r.offset()))
+    }
+
+    // Every value which this map sends somewhere in values
+    fn preimage(&self, values: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let values = values.ranges();
+        self.pieces()
+            .flat_map(|(range, offset)| {
+                let image = image(&range, offset);
+                // only the values which overlap this piece's image, moved back to where they came from
+                let first = values.partition_point(|v| v.end <= image.start);
+                values[first..]
+                    .iter()
+                    .take_while(move |v| v.start < image.end)
+                    .map(move |v| {
+                        let start = u64::max(v.start, image.start) as i128 - offset;
+                        let end = u64::min(v.end, image.end) as i128 - offset;
+                        start as u64..end as u64
+                    })
+            })
+            .collect()
+    }
+
+    // The lowest value this map sends any of values to. Works up through the pieces in order of
+    // where their images start, and stops once no later piece can land any lower.
+    fn lowest_image(&self, values: &IntervalSet<u64>) -> Option<u64> {
+        let mut pieces = self.pieces().collect::<Vec<_>>();
+        pieces.sort_by_key(|(range, offset)| image(range, *offset).start);
+
+        let values = values.ranges();
+        let mut lowest: Option<u64> = None;
+        for (range, offset) in pieces {
+            if lowest.is_some_and(|lowest| lowest <= image(&range, offset).start) {
+                break;
+            }
+            // a piece keeps values in order, so the first of them in it lands lowest
+            let i = values.partition_point(|v| v.end <= range.start);
+            if let Some(v) = values.get(i).filter(|v| v.start < range.end) {
+                let found = (u64::max(v.start, range.start) as i128 + offset) as u64;
+                lowest = Some(lowest.map_or(found, |lowest| lowest.min(found)));
+            }
+        }
+        lowest
+    }
+
+    // A single map which does the same as this one followed by next. Each rule of this map is
+    // split wherever its destination crosses a boundary between the rules of next.
+    fn then(&self, next: &WeirdMap) -> WeirdMap {
+        let mut rules: Vec<MappedRange> = Vec::new();
+
+        for (range, offset) in self.pieces() {
+            let image = image(&range, offset);
+            for next_rule in next.covering(image.clone()) {
+                let source_start =
+                    (u64::max(image.start, next_rule.source_start) as i128 - offset) as u64;
+                let source_end =
+                    (u64::min(image.end, next_rule.source_end) as i128 - offset) as u64;
+                let total = offset + next_rule.offset();
+                if total == 0 {
+                    continue;
+                }
+
+                // join up with the last rule when it moves values by the same amount
+                match rules.last_mut() {
+                    Some(last) if last.source_end == source_start && last.offset() == total => {
+                        last.source_end = source_end;
+                    }
+                    _ => rules.push(MappedRange {
+                        source_start,
+                        source_end,
+                        dest_start: (source_start as i128 + total) as u64,
+                    }),
+                }
+            }
         }
+
+        WeirdMap::new(rules)
     }
 }
 
+// Where a piece moved by offset ends up, leaving off anything which falls outside the u64s
+fn image(range: &Range<u64>, offset: i128) -> Range<u64> {
+    let clamp = |v: i128| v.clamp(0, u64::MAX as i128) as u64;
+    clamp(range.start as i128 + offset)..clamp(range.end as i128 + offset)
+}
+
+// Written the way the almanac writes it, one "<destination> <source> <length>" rule per line
+impl Display for WeirdMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, rule) in self.rules().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{} {} {}",
+                rule.dest_start,
+                rule.source_start,
+                rule.source_end - rule.source_start
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_gap_filled_layout() {
+    let map = WeirdMap::new(vec![
+        MappedRange {
+            source_start: 10,
+            source_end: 15,
+            dest_start: 20,
+        },
+        MappedRange {
+            source_start: 4,
+            source_end: 8,
+            dest_start: 0,
+        },
+        MappedRange {
+            source_start: 12,
+            source_end: 18,
+            dest_start: 30,
+        },
+    ]);
+
+    let pieces = map.pieces().collect::<Vec<_>>();
+    assert_eq!(
+        pieces,
+        vec![
+            (0..4, 0),
+            (4..8, -4),
+            (8..10, 0),
+            (10..15, 10),
+            // the overlapping rule only keeps the part the first one doesn't cover
+            (15..18, 18),
+            (18..u64::MAX, 0),
+        ]
+    );
+    assert_eq!(map.lookup(3), 3);
+    assert_eq!(map.lookup(4), 0);
+    assert_eq!(map.lookup(9), 9);
+    assert_eq!(map.lookup(14), 24);
+    assert_eq!(map.lookup(15), 33);
+    assert_eq!(map.lookup(u64::MAX), u64::MAX);
+    assert_eq!(map.rules().count(), 3);
+
+    // an empty rule leaves no piece behind
+    let map = WeirdMap::new(vec![MappedRange {
+        source_start: 5,
+        source_end: 5,
+        dest_start: 10,
+    }]);
+    assert_eq!(map.pieces().collect::<Vec<_>>(), vec![(0..u64::MAX, 0)]);
+}
+
+#[test]
+fn test_many_rules() {
+    // every other pair of values swaps places with the pair after it
+    let rules = (0..50_000u64)
+        .map(|i| MappedRange {
+            source_start: i * 2,
+            source_end: i * 2 + 2,
+            dest_start: if i % 2 == 0 { i * 2 + 2 } else { i * 2 - 2 },
+        })
+        .rev()
+        .collect();
+    let map = WeirdMap::new(rules);
+    assert_eq!(map.lookup(0), 2);
+    assert_eq!(map.lookup(3), 1);
+    assert_eq!(map.lookup(77_777), 77_779);
+    assert_eq!(map.lookup(100_000), 100_000);
+    // 1 goes to 3, 2 and 3 to 0 and 1, 4 and 5 to 6 and 7, and 6 to 4
+    assert_eq!(map.map(&(1..7).into()).ranges(), [0..2, 3..5, 6..8]);
+}
+
+#[test]
+fn test_lowest_image() {
+    let map = WeirdMap::new(vec![
+        MappedRange {
+            source_start: 0,
+            source_end: 100,
+            dest_start: 1000,
+        },
+        MappedRange {
+            source_start: 200,
+            source_end: 210,
+            dest_start: 1010,
+        },
+    ]);
+    // 0..100 is looked at first, but the piece starting at 1010 still lands lower
+    assert_eq!(map.lowest_image(&IntervalSet::from(90..95)), Some(1090));
+    assert_eq!(
+        map.lowest_image(&[90..95, 205..208].into_iter().collect()),
+        Some(1015)
+    );
+    // 100..200 stays where it is, below every rule
+    assert_eq!(map.lowest_image(&IntervalSet::from(150..300)), Some(150));
+    assert_eq!(map.lowest_image(&IntervalSet::new()), None);
+
+    // a forward search through every rule would find the same
+    let rules = (0..50_000u64)
+        .map(|i| MappedRange {
+            source_start: i * 10,
+            source_end: i * 10 + 10,
+            dest_start: (i * 7_919) % 50_000 * 10 + 1_000_000,
+        })
+        .collect();
+    let map = WeirdMap::new(rules);
+    let values = [5..6, 123_456..123_789, 400_000..400_001]
+        .into_iter()
+        .collect();
+    assert_eq!(map.lowest_image(&values), map.map(&values).min());
+}
+
+#[cfg(test)]
+fn mapped(map: &WeirdMap, source: Range<u64>) -> Vec<Range<u64>> {
+    map.map(&source.into()).ranges().to_vec()
+}
+
 #[test]
+#[allow(clippy::single_range_in_vec_init)]
 fn test_weirdmap_mapping() {
     let map = WeirdMap::new(vec![
         MappedRange {
@@ -57,11 +351,37 @@ fn test_weirdmap_mapping() {
         },
     ]);
 
-    assert_eq!(map.map(3..4), HashSet::from([3..4]));
-    assert_eq!(map.map(4..5), HashSet::from([10..11]));
-    assert_eq!(map.map(10..11), HashSet::from([20..21]));
-    assert_eq!(map.map(15..16), HashSet::from([15..16]));
-    assert_eq!(map.map(12..13), HashSet::from([30..31, 22..23]));
+    assert_eq!(mapped(&map, 3..4), [3..4]);
+    assert_eq!(mapped(&map, 4..5), [10..11]);
+    assert_eq!(mapped(&map, 10..11), [20..21]);
+    assert_eq!(mapped(&map, 15..16), [15..16]);
+    // the rules overlap here, and only the first one applies
+    assert_eq!(mapped(&map, 12..13), [22..23]);
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn test_weirdmap_splitting() {
+    let map = WeirdMap::new(vec![
+        MappedRange {
+            source_start: 20,
+            source_end: 30,
+            dest_start: 0,
+        },
+        MappedRange {
+            source_start: 5,
+            source_end: 10,
+            dest_start: 100,
+        },
+    ]);
+
+    // gaps either side and between the rules pass through, and the results are sorted and merged
+    assert_eq!(mapped(&map, 0..40), [0..20, 30..40, 100..105]);
+    assert_eq!(mapped(&map, 0..25), [0..5, 10..20, 100..105]);
+    // 20..30 becomes 0..10, which joins up with the unmapped 10..15
+    assert_eq!(mapped(&map, 10..30), [0..20]);
+    assert_eq!(mapped(&map, 7..8), [102..103]);
+    assert!(mapped(&map, 50..50).is_empty());
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -72,6 +392,26 @@ struct MappedRange {
 }
 
 impl MappedRange {
+    fn sources(&self) -> Range<u64> {
+        self.source_start..self.source_end
+    }
+
+    fn destinations(&self) -> Range<u64> {
+        self.dest_start..self.dest_start + (self.source_end - self.source_start)
+    }
+
+    fn identity(range: Range<u64>) -> Self {
+        MappedRange {
+            source_start: range.start,
+            dest_start: range.start,
+            source_end: range.end,
+        }
+    }
+
+    fn offset(&self) -> i128 {
+        self.dest_start as i128 - self.source_start as i128
+    }
+
     fn map_value(&self, v: u64) -> Option<u64> {
         Some(match self.source_start.cmp(&self.dest_start) {
             std::cmp::Ordering::Less => v + (self.dest_start - self.source_start),
@@ -80,51 +420,14 @@ impl MappedRange {
         })
     }
 
-    fn map(&self, source: Range<u64>) -> Option<HashSet<Range<u64>>> {
-        let mut did_map = false;
-        let mut ranges = HashSet::new();
-
-        // any unmapped range which exists before us
-        if source.start < self.source_start {
-            let end = u64::min(source.end, self.source_start);
-            ranges.insert(source.start..end);
-        }
-
-        // any range where we overlap
+    // Just the part of source this rule covers, moved to the destination
+    fn map(&self, source: Range<u64>) -> Option<Range<u64>> {
         let start = u64::max(source.start, self.source_start);
         let end = u64::min(source.end, self.source_end);
         if start < end {
-            did_map = true;
-            // now we need to map it into the destination range
-            match self.source_start.cmp(&self.dest_start) {
-                std::cmp::Ordering::Less => {
-                    let offset = self.dest_start - self.source_start;
-                    let start = start + offset;
-                    let end = end + offset;
-                    ranges.insert(start..end);
-                }
-                std::cmp::Ordering::Equal => {
-                    ranges.insert(start..end);
-                }
-                std::cmp::Ordering::Greater => {
-                    let offset = self.source_start - self.dest_start;
-                    let start = start - offset;
-                    let end = end - offset;
-                    ranges.insert(start..end);
-                }
-            }
-        }
-
-        // any unmapped range which exists after us
-        if source.end > self.source_end {
-            let start = u64::max(self.source_end, source.start);
-            ranges.insert(start..source.end);
-        }
-
-        if ranges.is_empty() || !did_map {
-            None
+            Some(self.map_value(start)?..self.map_value(end - 1)? + 1)
         } else {
-            Some(ranges)
+            None
         }
     }
 }
@@ -137,26 +440,49 @@ fn test_range_mapping() {
         dest_start: 10,
     };
     assert_eq!(range.map(3..4), None);
-    assert_eq!(range.map(4..5), Some(HashSet::from([10..11])));
-    assert_eq!(range.map(7..8), Some(HashSet::from([13..14])));
+    assert_eq!(range.map(4..5), Some(10..11));
+    assert_eq!(range.map(7..8), Some(13..14));
     assert_eq!(range.map(8..9), None);
+    assert_eq!(range.map(0..100), Some(10..14));
 }
 
 fn parse_range(input: &str) -> Result<MappedRange> {
-    let numbers: Vec<u64> = input
-        .split_ascii_whitespace()
-        .map(u64::from_str)
-        .collect::<std::result::Result<Vec<u64>, _>>()?;
-
-    if numbers.len() != 3 {
-        Err(format!("Input line '{}' does not contain three u64s", input).into())
-    } else {
-        Ok(MappedRange {
-            source_start: numbers[1],
-            dest_start: numbers[0],
-            source_end: numbers[1] + numbers[2],
-        })
+    let words = words(input).collect::<Vec<(usize, &str)>>();
+    if words.len() != 3 {
+        let column = words
+            .get(3)
+            .map(|&(column, _)| column)
+            .unwrap_or(input.len() + 1);
+        return Err(Error::parse(
+            1,
+            column,
+            input,
+            format!("expected three numbers but found {}", words.len()),
+        ));
     }
+
+    let numbers = words
+        .iter()
+        .map(|&(column, word)| parse_number(word, column, input))
+        .collect::<Result<Vec<u64>>>()?;
+
+    // a range can end at u64::MAX at the latest, since the end isn't included
+    for (i, name) in [(0, "destination"), (1, "source")] {
+        if numbers[i].checked_add(numbers[2]).is_none() {
+            return Err(Error::parse(
+                1,
+                words[i].0,
+                input,
+                format!("the {} range runs past {}", name, u64::MAX),
+            ));
+        }
+    }
+
+    Ok(MappedRange {
+        source_start: numbers[1],
+        dest_start: numbers[0],
+        source_end: numbers[1] + numbers[2],
+    })
 }
 
 #[test]
@@ -171,107 +497,360 @@ fn test_parse_range() {
     );
 }
 
+#[test]
+fn test_parse_range_errors() {
+    assert_eq!(
+        parse_range("0 18446744073709551615 1")
+            .unwrap_err()
+            .to_string(),
+        "Parse error at line 1, column 3: the source range runs past 18446744073709551615
+1 | 0 18446744073709551615 1
+  |   ^"
+    );
+    assert_eq!(
+        parse_range("18446744073709551610 0 10")
+            .unwrap_err()
+            .to_string(),
+        "Parse error at line 1, column 1: the destination range runs past 18446744073709551615
+1 | 18446744073709551610 0 10
+  | ^"
+    );
+    assert_eq!(
+        parse_range("50 98 2 7").unwrap_err().to_string(),
+        "Parse error at line 1, column 9: expected three numbers but found 4
+1 | 50 98 2 7
+  |         ^"
+    );
+    assert_eq!(
+        parse_range("50 98").unwrap_err().to_string(),
+        "Parse error at line 1, column 6: expected three numbers but found 2
+1 | 50 98
+  |      ^"
+    );
+    assert_eq!(
+        parse_range("50 -98 2").unwrap_err().to_string(),
+        "Parse error at line 1, column 4: '-98' is not a number: invalid digit found in string
+1 | 50 -98 2
+  |    ^"
+    );
+}
+
+// Everything parse_input rejects, plus rules which can't be what was meant: empty ones, and
+// ones which overlap another rule of the same map at either end
+fn check_almanac(input: &str) -> Vec<Error> {
+    let mut problems = Vec::new();
+    // each map's rules, with the line they're on
+    let mut maps: Vec<Vec<(usize, &str, MappedRange)>> = Vec::new();
+
+    for (i, line) in input.lines().enumerate().skip(1) {
+        if line.is_empty() {
+            continue;
+        }
+        if line.ends_with(" map:") {
+            maps.push(Vec::new());
+            continue;
+        }
+        let Some(rules) = maps.last_mut() else {
+            continue;
+        };
+        match parse_range(line) {
+            Ok(rule) if rule.source_start == rule.source_end => {
+                let column = words(line).nth(2).map_or(1, |(column, _)| column);
+                problems.push(Error::parse(i + 1, column, line, "the range is empty"));
+            }
+            Ok(rule) => rules.push((i + 1, line, rule)),
+            Err(e) => problems.push(e.on_line(i + 1)),
+        }
+    }
+
+    for rules in &mut maps {
+        // which end of the rule, and which word of the line it starts at
+        for (name, word, range) in [
+            (
+                "source",
+                1,
+                MappedRange::sources as fn(&MappedRange) -> Range<u64>,
+            ),
+            ("destination", 0, MappedRange::destinations),
+        ] {
+            rules.sort_by_key(|(_, _, rule)| range(rule).start);
+            // the earlier rule which reaches furthest, so any overlap is found
+            let mut furthest: Option<(usize, u64)> = None;
+            for (line_number, line, rule) in rules.iter() {
+                let range = range(rule);
+                match furthest {
+                    Some((other, end)) if range.start < end => {
+                        let column = words(line).nth(word).map_or(1, |(column, _)| column);
+                        problems.push(Error::parse(
+                            *line_number,
+                            column,
+                            line,
+                            format!("the {} range overlaps the rule on line {}", name, other),
+                        ));
+                        if range.end > end {
+                            furthest = Some((*line_number, range.end));
+                        }
+                    }
+                    _ => furthest = Some((*line_number, range.end)),
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        problems.extend(parse_input(input).err());
+    }
+    problems.sort_by_key(|e| match e {
+        Error::Parse { line, .. } => *line,
+        _ => 0,
+    });
+    problems
+}
+
+// One X-to-Y map section of the almanac
+#[derive(Debug)]
+struct Stage {
+    source: String,
+    destination: String,
+    map: WeirdMap,
+}
+
+// The stages in order, each one mapping from the category the last one mapped to
 #[derive(Debug)]
 struct WeirdMaps {
-    seed_to_soil: WeirdMap,
-    soil_to_fertiliser: WeirdMap,
-    fertiliser_to_water: WeirdMap,
-    water_to_light: WeirdMap,
-    light_to_temperature: WeirdMap,
-    temperature_to_humidity: WeirdMap,
-    humidity_to_location: WeirdMap,
+    stages: Vec<Stage>,
 }
 
 impl WeirdMaps {
-    fn locations_for_seed(&self, seed: Range<u64>) -> HashSet<Range<u64>> {
-        self.seed_to_soil
-            .map(seed)
+    fn categories(&self) -> impl Iterator<Item = &str> {
+        self.stages
+            .first()
+            .map(|stage| stage.source.as_str())
             .into_iter()
-            .flat_map(|i| self.soil_to_fertiliser.map(i))
-            .flat_map(|i| self.fertiliser_to_water.map(i))
-            .flat_map(|i| self.water_to_light.map(i))
-            .flat_map(|i| self.light_to_temperature.map(i))
-            .flat_map(|i| self.temperature_to_humidity.map(i))
-            .flat_map(|i| self.humidity_to_location.map(i))
-            .collect()
+            .chain(self.stages.iter().map(|stage| stage.destination.as_str()))
     }
+
+    fn position(&self, category: &str) -> Result<usize> {
+        self.categories()
+            .position(|c| c == category)
+            .ok_or_else(|| format!("There is no '{}' category", category).into())
+    }
+
+    #[cfg(test)]
+    fn map_between(
+        &self,
+        from: &str,
+        to: &str,
+        values: IntervalSet<u64>,
+    ) -> Result<IntervalSet<u64>> {
+        Ok(self.compose(from, to)?.map(&values))
+    }
+
+    // The same as map_between but a stage at a time, so the ranges in each category can be traced
+    fn trace_between(&self, from: &str, to: &str, values: IntervalSet<u64>) -> Result<()> {
+        let (start, end) = (self.position(from)?, self.position(to)?);
+        trace!("{}: {:?}", from, values.ranges());
+        self.stages[start..end]
+            .iter()
+            .fold(values, |values, stage| {
+                let values = stage.map.map(&values);
+                trace!("{}: {:?}", stage.destination, values.ranges());
+                values
+            });
+        Ok(())
+    }
+
+    // All the stages from one category to another collapsed into a single map
+    fn compose(&self, from: &str, to: &str) -> Result<WeirdMap> {
+        let (start, end) = (self.position(from)?, self.position(to)?);
+        if end < start {
+            return Err(format!("'{}' comes before '{}'", to, from).into());
+        }
+
+        let composed = self.stages[start..end]
+            .iter()
+            .fold(WeirdMap::new(Vec::new()), |composed, stage| {
+                composed.then(&stage.map)
+            });
+        debug!(
+            "{}-to-{} map has {} rules",
+            from,
+            to,
+            composed.rules().count()
+        );
+        Ok(composed)
+    }
+
+    // One binary search per stage
+    fn location_for_seed(&self, seed: u64) -> Result<u64> {
+        let (start, end) = (self.position("seed")?, self.position("location")?);
+        Ok(self.stages[start..end]
+            .iter()
+            .fold(seed, |value, stage| stage.map.lookup(value)))
+    }
+
+    #[cfg(test)]
+    fn locations_for_seed(&self, seeds: IntervalSet<u64>) -> Result<IntervalSet<u64>> {
+        self.map_between("seed", "location", seeds)
+    }
+
+    // The seeds which end up somewhere in locations
+    #[cfg(test)]
+    fn seeds_for_locations(&self, locations: IntervalSet<u64>) -> Result<IntervalSet<u64>> {
+        Ok(self.compose("seed", "location")?.preimage(&locations))
+    }
+
+    // Searches back from the lowest locations for the first one which a seed reaches
+    fn lowest_location(&self, seeds: &IntervalSet<u64>) -> Result<Option<u64>> {
+        let composed = self.compose("seed", "location")?;
+        let lowest = composed.lowest_image(seeds);
+        if let (Some(lowest), true) = (lowest, trace::enabled(trace::Level::Debug)) {
+            let reaching = composed
+                .preimage(&(lowest..lowest + 1).into())
+                .intersection(seeds);
+            debug!("seeds {:?} reach location {}", reaching.ranges(), lowest);
+        }
+        Ok(lowest)
+    }
+}
+
+// A header looks like "seed-to-soil map:"
+fn parse_header(line: &str) -> Result<(String, String)> {
+    line.strip_suffix(" map:")
+        .and_then(|name| name.split_once("-to-"))
+        .filter(|(source, destination)| !source.is_empty() && !destination.is_empty())
+        .map(|(source, destination)| (source.to_owned(), destination.to_owned()))
+        .ok_or_else(|| Error::parse(1, 1, line, "expected '<source>-to-<destination> map:'"))
 }
 
 fn parse_input(input: &str) -> Result<(Vec<u64>, WeirdMaps)> {
-    let mut lines = input.lines();
-    let first_line = lines.next().unwrap();
-    let seeds = first_line
-        .trim_start_matches("seeds: ")
-        .split_ascii_whitespace()
-        .map(u64::from_str)
-        .collect::<std::result::Result<Vec<u64>, _>>()?;
-    lines.next();
-    lines.next();
-
-    let mut current_ranges = Vec::new();
-    let mut current_maps = Vec::new();
-
-    for line in lines {
+    let mut lines = input.lines().enumerate();
+    let (_, first_line) = lines
+        .next()
+        .ok_or_else(|| Error::parse(1, 1, input, "the almanac is empty"))?;
+    if !first_line.starts_with("seeds:") {
+        return Err(Error::parse(1, 1, first_line, "expected 'seeds:'"));
+    }
+    let seed_words = words(first_line).skip(1).collect::<Vec<(usize, &str)>>();
+    let seeds = seed_words
+        .iter()
+        .map(|&(column, word)| parse_number(word, column, first_line))
+        .collect::<Result<Vec<u64>>>()?;
+
+    // part 2 reads the seeds as pairs of start and length, which have to stay within a u64 too
+    for (pair, words) in seeds.chunks_exact(2).zip(seed_words.chunks_exact(2)) {
+        if pair[0].checked_add(pair[1]).is_none() {
+            return Err(Error::parse(
+                1,
+                words[0].0,
+                first_line,
+                format!("the seed range runs past {}", u64::MAX),
+            ));
+        }
+    }
+
+    // each map's source, destination and rules, which only become a WeirdMap once they're all read
+    let mut sections: Vec<(String, String, Vec<MappedRange>)> = Vec::new();
+
+    for (i, line) in lines {
         if line.is_empty() {
             continue;
         }
         if line.ends_with(" map:") {
-            current_maps.push(WeirdMap::new(current_ranges));
-            current_ranges = Vec::new();
+            let (source, destination) = parse_header(line).map_err(|e| e.on_line(i + 1))?;
+
+            // the seeds line says where the chain starts
+            let expected = sections
+                .last()
+                .map_or("seed", |(_, destination, _)| destination);
+            if source != expected {
+                return Err(Error::parse(
+                    i + 1,
+                    1,
+                    line,
+                    format!("expected a map from '{}'", expected),
+                ));
+            }
+            if destination == "seed" || sections.iter().any(|(_, d, _)| *d == destination) {
+                return Err(Error::parse(
+                    i + 1,
+                    source.len() + 5,
+                    line,
+                    format!("'{}' has already been mapped to", destination),
+                ));
+            }
+
+            sections.push((source, destination, Vec::new()));
             continue;
         }
-        let range = parse_range(line)?;
-        current_ranges.push(range);
+        let (_, _, rules) = sections
+            .last_mut()
+            .ok_or_else(|| Error::parse(i + 1, 1, line, "expected a map header before ranges"))?;
+        let range = parse_range(line).map_err(|e| e.on_line(i + 1))?;
+        rules.push(range);
     }
 
-    current_maps.push(WeirdMap::new(current_ranges));
-
-    if current_maps.len() != 7 {
-        return Err(format!(
-            "There should have been seven sets of ranges, but I only got {}",
-            current_maps.len()
-        )
-        .into());
-    }
-
-    Ok((
-        seeds,
-        WeirdMaps {
-            seed_to_soil: current_maps[0].clone(),
-            soil_to_fertiliser: current_maps[1].clone(),
-            fertiliser_to_water: current_maps[2].clone(),
-            water_to_light: current_maps[3].clone(),
-            light_to_temperature: current_maps[4].clone(),
-            temperature_to_humidity: current_maps[5].clone(),
-            humidity_to_location: current_maps[6].clone(),
-        },
-    ))
+    let stages = sections
+        .into_iter()
+        .map(|(source, destination, rules)| Stage {
+            source,
+            destination,
+            map: WeirdMap::new(rules),
+        })
+        .collect();
+    Ok((seeds, WeirdMaps { stages }))
 }
 
-fn part1(input: &str) -> Result<u64> {
-    let (seeds, weirdmaps) = parse_input(input)?;
-    let ranges = seeds
-        .into_iter()
-        .flat_map(|s| weirdmaps.locations_for_seed(s..s + 1));
-    let first_range = ranges.min_by_key(|r| r.start).map(|r| r.start);
+fn part1(seeds: &[u64], weirdmaps: &WeirdMaps) -> Result<u64> {
+    let locations = seeds
+        .iter()
+        .map(|&seed| weirdmaps.location_for_seed(seed))
+        .collect::<Result<Vec<u64>>>()?;
 
-    Ok(first_range.ok_or("No location mapped".to_owned())?)
+    Ok(locations
+        .into_iter()
+        .min()
+        .ok_or("No location mapped".to_owned())?)
 }
 
-fn part2(input: &str) -> Result<u64> {
-    let (seeds, weirdmaps) = parse_input(input)?;
+fn part2(seeds: &[u64], weirdmaps: &WeirdMaps) -> Result<u64> {
+    if !seeds.len().is_multiple_of(2) {
+        return Err("Seeds must come in pairs of start and length".into());
+    }
     let ranges: Vec<Range<u64>> = seeds
         .chunks_exact(2)
         .map(|chunk| chunk[0]..chunk[0] + chunk[1])
         .collect();
-    println!("{:?}", ranges);
-    let range = ranges
-        .into_iter()
-        .flat_map(|s| weirdmaps.locations_for_seed(s))
-        .min_by_key(|r| r.start);
-    Ok(range
-        .expect("There should at least be a range, right?")
-        .start)
+    let seeds: IntervalSet<u64> = ranges.into_iter().collect();
+    debug!("{} seeds in {} ranges", seeds.len(), seeds.ranges().len());
+    if trace::enabled(trace::Level::Trace) {
+        weirdmaps.trace_between("seed", "location", seeds.clone())?;
+    }
+    let lowest = weirdmaps.lowest_location(&seeds)?;
+    Ok(lowest.ok_or("No location mapped")?)
+}
+
+// The whole seed-to-location chain collapsed into one map
+fn report(input: &str, format: Format) -> Result<String> {
+    let (_seeds, weirdmaps) = parse_input(input)?;
+    let composed = weirdmaps.compose("seed", "location")?;
+
+    Ok(match format {
+        Format::Text => format!("seed-to-location map:\n{}", composed),
+        Format::Json => Json::Array(
+            composed
+                .rules()
+                .map(|rule| {
+                    Json::object([
+                        ("source_start", Json::number(rule.source_start)),
+                        ("source_end", Json::number(rule.source_end)),
+                        ("dest_start", Json::number(rule.dest_start)),
+                    ])
+                })
+                .collect(),
+        )
+        .to_string(),
+    })
 }
 
 #[cfg(test)]
@@ -311,20 +890,389 @@ humidity-to-location map:
 
 #[test]
 fn test_part1() {
-    let r = part1(TEST_INPUT).unwrap();
+    let (seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
+    let r = part1(&seeds, &weirdmaps).unwrap();
     assert_eq!(r, 35);
 }
 
 #[test]
+#[allow(clippy::single_range_in_vec_init)]
 fn test_first_seed() {
     let (_seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
-    let l = weirdmaps.locations_for_seed(79..80);
-    assert_eq!(l.len(), 1);
-    assert_eq!(l.into_iter().next().unwrap(), 82..83);
+    let l = weirdmaps.locations_for_seed((79..80).into()).unwrap();
+    assert_eq!(l.ranges(), [82..83]);
+}
+
+#[test]
+fn test_bad_almanacs() {
+    let error = |input: &str| parse_input(input).unwrap_err().to_string();
+    assert_eq!(
+        error(""),
+        "Parse error at line 1, column 1: the almanac is empty
+1 |
+  | ^"
+    );
+    assert_eq!(
+        error("79 14 55 13"),
+        "Parse error at line 1, column 1: expected 'seeds:'
+1 | 79 14 55 13
+  | ^"
+    );
+    assert_eq!(
+        error("seeds: 79 14\n\n50 98 2\nseed-to-soil map:"),
+        "Parse error at line 3, column 1: expected a map header before ranges
+3 | 50 98 2
+  | ^"
+    );
+    assert_eq!(
+        error("seeds: 79 14\n\nseed-to-soil map:\n50 98 x"),
+        "Parse error at line 4, column 7: 'x' is not a number: invalid digit found in string
+4 | 50 98 x
+  |       ^"
+    );
+    assert_eq!(
+        error("seeds: 79 14 18446744073709551615 5\n\nseed-to-soil map:\n50 98 2"),
+        "Parse error at line 1, column 14: the seed range runs past 18446744073709551615
+1 | seeds: 79 14 18446744073709551615 5
+  |              ^"
+    );
+}
+
+#[test]
+fn test_bad_chains() {
+    let error = |input: &str| parse_input(input).unwrap_err().to_string();
+    assert_eq!(
+        error("seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n\nwater-to-light map:\n1 2 3"),
+        "Parse error at line 6, column 1: expected a map from 'soil'
+6 | water-to-light map:
+  | ^"
+    );
+    assert_eq!(
+        error("seeds: 79 14\n\nsoil-to-water map:"),
+        "Parse error at line 3, column 1: expected a map from 'seed'
+3 | soil-to-water map:
+  | ^"
+    );
+    assert_eq!(
+        error("seeds: 79 14\n\nseed-to-soil map:\nsoil-to-seed map:"),
+        "Parse error at line 4, column 9: 'seed' has already been mapped to
+4 | soil-to-seed map:
+  |         ^"
+    );
+    assert_eq!(
+        error("seeds: 79 14\n\nseed to soil map:"),
+        "Parse error at line 3, column 1: expected '<source>-to-<destination> map:'
+3 | seed to soil map:
+  | ^"
+    );
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn test_map_between() {
+    let (_seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
+    assert_eq!(
+        weirdmaps.categories().collect::<Vec<_>>(),
+        vec![
+            "seed",
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location"
+        ]
+    );
+    // seed 79 is soil 81, fertilizer 81, water 81, light 74, temperature 78, humidity 78
+    let between = |from, to| weirdmaps.map_between(from, to, (79..80).into()).unwrap();
+    assert_eq!(between("seed", "soil").ranges(), [81..82]);
+    assert_eq!(between("seed", "light").ranges(), [74..75]);
+    assert_eq!(between("water", "humidity").ranges(), [76..77]);
+    assert_eq!(between("soil", "soil").ranges(), [79..80]);
+    assert_eq!(
+        weirdmaps
+            .map_between("light", "soil", (79..80).into())
+            .unwrap_err()
+            .to_string(),
+        "'soil' comes before 'light'"
+    );
+    assert_eq!(
+        weirdmaps
+            .map_between("seed", "gold", (79..80).into())
+            .unwrap_err()
+            .to_string(),
+        "There is no 'gold' category"
+    );
+
+    // any number of stages is fine, as long as they chain
+    let (_seeds, short) = parse_input("seeds: 1\n\nseed-to-location map:\n10 0 5").unwrap();
+    assert_eq!(
+        short.locations_for_seed((1..3).into()).unwrap().ranges(),
+        [11..13]
+    );
 }
 
 #[test]
 fn test_part2() {
-    let r = part2(TEST_INPUT).unwrap();
+    let (seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
+    let r = part2(&seeds, &weirdmaps).unwrap();
     assert_eq!(r, 46);
 }
+
+#[cfg(test)]
+fn map_one(map: &WeirdMap, value: u64) -> u64 {
+    let mapped = map.map(&(value..value + 1).into()).min().unwrap();
+    assert_eq!(mapped, map.lookup(value));
+    mapped
+}
+
+#[test]
+fn test_compose() {
+    let (_seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
+    let composed = weirdmaps.compose("seed", "location").unwrap();
+    for seed in 0..200 {
+        let location = weirdmaps
+            .map_between("seed", "location", (seed..seed + 1).into())
+            .unwrap();
+        assert_eq!(
+            map_one(&composed, seed),
+            location.min().unwrap(),
+            "seed {}",
+            seed
+        );
+    }
+    assert_eq!(map_one(&composed, 79), 82);
+
+    let soil_to_water = weirdmaps.compose("soil", "water").unwrap();
+    let soil_to_fertilizer = &weirdmaps.stages[1].map;
+    let fertilizer_to_water = &weirdmaps.stages[2].map;
+    for soil in 0..100 {
+        assert_eq!(
+            map_one(&soil_to_water, soil),
+            map_one(fertilizer_to_water, map_one(soil_to_fertilizer, soil))
+        );
+    }
+
+    // composing nothing leaves every value where it is
+    assert_eq!(
+        weirdmaps.compose("soil", "soil").unwrap().rules().count(),
+        0
+    );
+}
+
+#[test]
+fn test_compose_display() {
+    let first = WeirdMap::new(vec![MappedRange {
+        source_start: 0,
+        source_end: 10,
+        dest_start: 100,
+    }]);
+    let second = WeirdMap::new(vec![
+        MappedRange {
+            source_start: 95,
+            source_end: 105,
+            dest_start: 0,
+        },
+        MappedRange {
+            source_start: 5,
+            source_end: 10,
+            dest_start: 6,
+        },
+    ]);
+
+    // 0..5 goes to 100..105 then 5..10, and 5..10 goes to 105..110 where second leaves it.
+    // Nothing reaches second's 5..10 rule any more, but 95..105 still gets to its other rule.
+    assert_eq!(
+        first.then(&second).to_string(),
+        "5 0 5
+105 5 5
+0 95 10"
+    );
+    // 0..10 ends up back where it started so needs no rule, while 100..110 is still moved
+    let back = WeirdMap::new(vec![MappedRange {
+        source_start: 100,
+        source_end: 110,
+        dest_start: 0,
+    }]);
+    assert_eq!(first.then(&back).to_string(), "0 100 10");
+
+    // neighbouring rules which move values by the same amount are joined up
+    let split = WeirdMap::new(vec![
+        MappedRange {
+            source_start: 5,
+            source_end: 10,
+            dest_start: 15,
+        },
+        MappedRange {
+            source_start: 0,
+            source_end: 5,
+            dest_start: 10,
+        },
+    ]);
+    assert_eq!(
+        split.then(&WeirdMap::new(Vec::new())).to_string(),
+        "10 0 10"
+    );
+}
+
+#[test]
+fn test_report() {
+    // seeds 0..2 end up back where they started, so need no rule
+    assert_eq!(
+        report(
+            "seeds: 1\n\nseed-to-soil map:\n10 0 5\n\nsoil-to-location map:\n0 10 2",
+            Format::Text
+        )
+        .unwrap(),
+        "seed-to-location map:
+12 2 3
+0 10 2"
+    );
+    assert_eq!(
+        report("seeds: 1\n\nseed-to-location map:\n10 0 5", Format::Json).unwrap(),
+        r#"[{"source_start":0,"source_end":5,"dest_start":10}]"#
+    );
+    assert_eq!(
+        report(
+            "seeds: 1\n\nseed-to-location map:\n10 5 0\n20 0 5",
+            Format::Text
+        )
+        .unwrap(),
+        "seed-to-location map:\n20 0 5"
+    );
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn test_preimage() {
+    let (_seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
+    assert_eq!(
+        weirdmaps
+            .seeds_for_locations((82..83).into())
+            .unwrap()
+            .ranges(),
+        [79..80]
+    );
+    assert_eq!(
+        weirdmaps
+            .compose("light", "humidity")
+            .unwrap()
+            .preimage(&(78..79).into())
+            .ranges(),
+        [74..75]
+    );
+
+    // 15..20 and the untouched 5..10 both land in 5..10, so both come back
+    let squash = WeirdMap::new(vec![MappedRange {
+        source_start: 10,
+        source_end: 20,
+        dest_start: 0,
+    }]);
+    assert_eq!(squash.preimage(&(5..15).into()).ranges(), [5..10, 15..20]);
+    assert_eq!(squash.preimage(&(100..110).into()).ranges(), [100..110]);
+    assert_eq!(
+        squash.preimage(&(u64::MAX - 1..u64::MAX).into()).ranges(),
+        [u64::MAX - 1..u64::MAX]
+    );
+}
+
+#[test]
+fn test_round_trips() {
+    let (seeds, weirdmaps) = parse_input(TEST_INPUT).unwrap();
+    let seeds: IntervalSet<u64> = seeds
+        .chunks_exact(2)
+        .map(|chunk| chunk[0]..chunk[0] + chunk[1])
+        .collect();
+
+    // the example's maps never send two seeds to the same place, so nothing extra comes back
+    let locations = weirdmaps.locations_for_seed(seeds.clone()).unwrap();
+    assert_eq!(
+        weirdmaps.seeds_for_locations(locations.clone()).unwrap(),
+        seeds
+    );
+
+    // and everything a location range's seeds reach is back inside that range
+    let wanted = IntervalSet::from(40..70);
+    let found = weirdmaps.seeds_for_locations(wanted.clone()).unwrap();
+    let reached = weirdmaps.locations_for_seed(found).unwrap();
+    assert_eq!(reached.difference(&wanted), IntervalSet::new());
+    assert_eq!(reached, wanted);
+
+    // the seeds which reach the lowest location
+    let lowest = IntervalSet::from(46..47);
+    assert_eq!(locations.min(), Some(46));
+    assert_eq!(
+        weirdmaps
+            .seeds_for_locations(lowest)
+            .unwrap()
+            .intersection(&seeds),
+        IntervalSet::from(82..83)
+    );
+
+    assert_eq!(weirdmaps.lowest_location(&seeds).unwrap(), Some(46));
+    assert_eq!(
+        weirdmaps.lowest_location(&IntervalSet::new()).unwrap(),
+        None
+    );
+    for seed in [0, 13, 14, 55, 79, 98, 99] {
+        let seed = IntervalSet::from(seed..seed + 1);
+        assert_eq!(
+            weirdmaps.lowest_location(&seed).unwrap(),
+            weirdmaps.locations_for_seed(seed).unwrap().min()
+        );
+    }
+}
+
+#[test]
+fn test_check_almanac() {
+    assert!(check_almanac(TEST_INPUT).is_empty());
+
+    let input = "seeds: 79 14
+
+seed-to-soil map:
+50 98 2
+52 50 48
+60 40 20
+0 18446744073709551615 1
+7 7 0
+
+soil-to-fertilizer map:
+0 15 37
+10 52 2
+39 x 15";
+    let problems = check_almanac(input)
+        .into_iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        vec![
+            "Parse error at line 5, column 4: the source range overlaps the rule on line 6
+5 | 52 50 48
+  |    ^",
+            "Parse error at line 6, column 1: the destination range overlaps the rule on line 5
+6 | 60 40 20
+  | ^",
+            "Parse error at line 7, column 3: the source range runs past 18446744073709551615
+7 | 0 18446744073709551615 1
+  |   ^",
+            "Parse error at line 8, column 5: the range is empty
+8 | 7 7 0
+  |     ^",
+            "Parse error at line 12, column 1: the destination range overlaps the rule on line 11
+12 | 10 52 2
+   | ^",
+            "Parse error at line 13, column 4: 'x' is not a number: invalid digit found in string
+13 | 39 x 15
+   |    ^",
+        ]
+    );
+
+    // with nothing wrong with the rules, the rest of the almanac is still checked
+    let problems = check_almanac("seeds: 1\n\nsoil-to-water map:\n1 2 3");
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0]
+        .to_string()
+        .starts_with("Parse error at line 3, column 1: expected a map from 'seed'"));
+}
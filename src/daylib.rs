@@ -1,9 +1,230 @@
-use std::{error::Error, fmt::Display};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-pub(crate) type Result<T> = std::result::Result<T, Box<dyn Error>>;
-pub(crate) type DayResult = Result<Parts>;
+pub use crate::error::Error;
 
-pub(crate) struct Parts {
-    pub part1: Box<dyn Display>,
-    pub part2: Box<dyn Display>,
+pub type Result<T> = std::result::Result<T, Error>;
+pub type DayResult = Result<Parts>;
+
+pub struct Parts {
+    pub part1: Part,
+    pub part2: Part,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Integer(i128),
+    Text(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(i) => write!(f, "{}", i),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! integer_answer {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(value: $t) -> Self {
+                Answer::Integer(value.into())
+            }
+        })*
+    };
+}
+
+integer_answer!(u8, u16, u32, u64, i32, i64);
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Integer(value as i128)
+    }
+}
+
+impl FromStr for Answer {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Err(Error::parse(1, 1, s, "an answer can't be empty"));
+        }
+        Ok(s.parse::<i128>()
+            .map(Answer::Integer)
+            .unwrap_or_else(|_| Answer::Text(s.to_owned())))
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::Text(value.to_owned())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub answer: Answer,
+    pub description: Option<String>,
+}
+
+impl Part {
+    pub fn new(answer: impl Into<Answer>) -> Self {
+        Part {
+            answer: answer.into(),
+            description: None,
+        }
+    }
+
+    pub fn described(answer: impl Into<Answer>, description: &str) -> Self {
+        Part {
+            answer: answer.into(),
+            description: Some(description.to_owned()),
+        }
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{}: {}", description, self.answer),
+            None => write!(f, "{}", self.answer),
+        }
+    }
+}
+
+// Each whitespace separated word in a line, with its 1-based column
+pub fn words(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_ascii_whitespace()
+        .map(move |word| (word.as_ptr() as usize - line.as_ptr() as usize + 1, word))
+}
+
+pub fn parse_number<T>(word: &str, column: usize, line: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    word.parse::<T>().map_err(|e| {
+        Error::parse(
+            1,
+            column,
+            line,
+            format!("'{}' is not a number: {}", word, e),
+        )
+    })
+}
+
+pub trait Solver {
+    type Input;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+    fn part1(input: &Self::Input) -> Result<Part>;
+    fn part2(input: &Self::Input) -> Result<Part>;
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Duration,
+    pub part2: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.part1 + self.part2
+    }
+}
+
+pub struct Day {
+    pub year: u16,
+    pub day: u8,
+    pub run: fn(&str, &mut Timings) -> DayResult,
+    // Stricter validation for --strict, which reports every problem in the input
+    pub check: Option<fn(&str) -> Vec<Error>>,
+    // A detailed breakdown of the answers for --report
+    pub report: Option<fn(&str, Format) -> Result<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Day {
+    pub const fn new<S: Solver>(year: u16, day: u8) -> Self {
+        Day {
+            year,
+            day,
+            run: run::<S>,
+            check: None,
+            report: None,
+        }
+    }
+
+    pub const fn with_check(self, check: fn(&str) -> Vec<Error>) -> Self {
+        Day {
+            check: Some(check),
+            ..self
+        }
+    }
+
+    pub const fn with_report(self, report: fn(&str, Format) -> Result<String>) -> Self {
+        Day {
+            report: Some(report),
+            ..self
+        }
+    }
+}
+
+fn run<S: Solver>(input: &str, timings: &mut Timings) -> DayResult {
+    let parsed = time(&mut timings.parse, || S::parse(input))?;
+    let part1 = time(&mut timings.part1, || S::part1(&parsed))?;
+    let part2 = time(&mut timings.part2, || S::part2(&parsed))?;
+
+    Ok(Parts { part1, part2 })
+}
+
+fn time<T>(elapsed: &mut Duration, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *elapsed = start.elapsed();
+    result
+}
+
+#[test]
+fn test_part_display() {
+    assert_eq!(Part::new(142u32).to_string(), "142");
+    assert_eq!(
+        Part::described(281u64, "Sum of the wordy digits").to_string(),
+        "Sum of the wordy digits: 281"
+    );
+    assert_eq!(
+        Part::new("Not implemented").answer,
+        Answer::Text("Not implemented".to_owned())
+    );
+    assert_eq!(
+        Answer::from(u64::MAX),
+        Answer::Integer(18446744073709551615)
+    );
+}
+
+#[test]
+fn test_words() {
+    let line = "Card  3:  1 21";
+    assert_eq!(
+        words(line).collect::<Vec<_>>(),
+        vec![(1, "Card"), (7, "3:"), (11, "1"), (13, "21")]
+    );
+    assert_eq!(parse_number::<u8>("21", 13, line).unwrap(), 21);
+    assert_eq!(
+        parse_number::<u8>("300", 13, line).unwrap_err().to_string(),
+        "Parse error at line 1, column 13: '300' is not a number: number too large to fit in target type
+1 | Card  3:  1 21
+  |             ^"
+    );
 }
@@ -0,0 +1,167 @@
+use std::{
+    fmt::{self, Display},
+    io,
+    path::PathBuf,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    // line and column are 1-based, and text is the whole offending line. The column counts bytes
+    // so parsers can slice with it, and is shown counting characters.
+    Parse {
+        line: usize,
+        column: usize,
+        text: String,
+        message: String,
+    },
+    UnsupportedDay {
+        year: u16,
+        day: u8,
+    },
+    MissingInput(PathBuf),
+    Io(io::Error),
+    Solver(String),
+}
+
+impl Error {
+    pub fn parse(line: usize, column: usize, text: &str, message: impl Into<String>) -> Self {
+        Error::Parse {
+            line,
+            column,
+            text: text.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    // Line parsers report line 1, so callers move the error onto the line they were given
+    pub fn on_line(self, line: usize) -> Self {
+        match self {
+            Error::Parse {
+                column,
+                text,
+                message,
+                ..
+            } => Error::Parse {
+                line,
+                column,
+                text,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse {
+                line,
+                column,
+                text,
+                message,
+            } => {
+                let column = char_column(text, *column);
+                writeln!(
+                    f,
+                    "Parse error at line {}, column {}: {}",
+                    line, column, message
+                )?;
+                write_snippet(f, *line, column, text)
+            }
+            Error::UnsupportedDay { year, day } => {
+                write!(f, "No solver for day {} of {}", day, year)
+            }
+            Error::MissingInput(path) => write!(f, "Input file {} does not exist", path.display()),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Solver(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// The 1-based character column of a 1-based byte column
+fn char_column(text: &str, column: usize) -> usize {
+    let offset = column.saturating_sub(1);
+    let before = text
+        .get(..offset)
+        .map(|before| before.chars().count())
+        .unwrap_or(offset);
+    before + 1
+}
+
+// Shows the offending line with a caret under the column, like
+//   2 | Game 2: 1 red; 99999999999 green
+//     |                ^
+fn write_snippet(
+    f: &mut fmt::Formatter<'_>,
+    line: usize,
+    column: usize,
+    text: &str,
+) -> fmt::Result {
+    let gutter = line.to_string();
+    writeln!(f, "{}", format!("{} | {}", gutter, text).trim_end())?;
+    write!(
+        f,
+        "{} | {}^",
+        " ".repeat(gutter.len()),
+        " ".repeat(column.saturating_sub(1))
+    )
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(value: &str) -> Self {
+        Error::Solver(value.to_owned())
+    }
+}
+
+impl From<String> for Error {
+    fn from(value: String) -> Self {
+        Error::Solver(value)
+    }
+}
+
+#[test]
+fn test_on_line() {
+    let e = Error::parse(1, 4, "1 2 x", "bad number").on_line(7);
+    assert_eq!(
+        e.to_string(),
+        "Parse error at line 7, column 4: bad number
+7 | 1 2 x
+  |    ^"
+    );
+
+    let e = Error::parse(12, 1, "", "empty line");
+    assert_eq!(
+        e.to_string(),
+        "Parse error at line 12, column 1: empty line
+12 |
+   | ^"
+    );
+
+    // "é" takes two bytes, but the column counts it once
+    let e = Error::parse(1, 8, "é 1 |x", "no colon");
+    assert_eq!(
+        e.to_string(),
+        "Parse error at line 1, column 7: no colon
+1 | é 1 |x
+  |       ^"
+    );
+
+    let e = Error::from("Something went wrong").on_line(7);
+    assert_eq!(e.to_string(), "Something went wrong");
+}
@@ -0,0 +1,398 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+use crate::daylib::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl Point {
+    pub fn new(row: usize, column: usize) -> Self {
+        Point { row, column }
+    }
+
+    fn offset(self, rows: isize, columns: isize) -> Option<Point> {
+        Some(Point {
+            row: self.row.checked_add_signed(rows)?,
+            column: self.column.checked_add_signed(columns)?,
+        })
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.row, self.column)
+    }
+}
+
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const ALL_AROUND: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// A rectangular grid stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    // Each line of the input is a row, and every row must be the same width
+    pub fn parse_with(
+        input: &str,
+        mut cell: impl FnMut(char) -> std::result::Result<T, String>,
+    ) -> Result<Self> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let mut row_width = 0;
+            for (offset, c) in line.char_indices() {
+                cells.push(
+                    cell(c).map_err(|message| Error::parse(i + 1, offset + 1, line, message))?,
+                );
+                row_width += 1;
+            }
+
+            let expected = *width.get_or_insert(row_width);
+            if row_width != expected {
+                let column = line
+                    .char_indices()
+                    .nth(expected)
+                    .map(|(offset, _)| offset)
+                    .unwrap_or(line.len());
+                return Err(Error::parse(
+                    i + 1,
+                    column + 1,
+                    line,
+                    format!("expected {} columns but found {}", expected, row_width),
+                ));
+            }
+            height += 1;
+        }
+
+        match width {
+            Some(width) if width > 0 => Ok(Grid {
+                width,
+                height,
+                cells,
+            }),
+            _ => Err(Error::parse(1, 1, input, "the grid is empty")),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.row < self.height && point.column < self.width
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        if self.contains(point) {
+            Some(&self.cells[point.row * self.width + point.column])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        if self.contains(point) {
+            Some(&mut self.cells[point.row * self.width + point.column])
+        } else {
+            None
+        }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |column| Point { row, column }))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row < self.height {
+            Some(&self.cells[row * self.width..(row + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    // A grid made with no columns still has its rows, they're just empty
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|row| &self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        let cells = if column < self.width {
+            &self.cells[column..]
+        } else {
+            &[]
+        };
+        cells.iter().step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|column| self.column(column))
+    }
+
+    // Up, left, right and down, leaving out any which fall off the grid
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        self.neighbours(point, &ORTHOGONAL)
+    }
+
+    // The neighbours4 and the diagonals, in reading order
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        self.neighbours(point, &ALL_AROUND)
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        point: Point,
+        directions: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = Point> + 'a {
+        directions
+            .iter()
+            .filter_map(move |&(rows, columns)| point.offset(rows, columns))
+            .filter(|&p| self.contains(p))
+    }
+
+    // The orthogonally connected cells around start which satisfy include, in the order found
+    pub fn region(&self, start: Point, include: impl Fn(&T) -> bool) -> Vec<Point> {
+        let mut seen = HashSet::new();
+        self.flood(start, &include, &mut seen)
+    }
+
+    // Every orthogonally connected region of cells which satisfy include
+    pub fn regions(&self, include: impl Fn(&T) -> bool) -> Vec<Vec<Point>> {
+        let mut seen = HashSet::new();
+        let mut regions = Vec::new();
+        for point in self.points() {
+            if !seen.contains(&point) && include(&self[point]) {
+                regions.push(self.flood(point, &include, &mut seen));
+            }
+        }
+        regions
+    }
+
+    fn flood(
+        &self,
+        start: Point,
+        include: &impl Fn(&T) -> bool,
+        seen: &mut HashSet<Point>,
+    ) -> Vec<Point> {
+        let mut region = Vec::new();
+        let mut to_visit = vec![start];
+        while let Some(point) = to_visit.pop() {
+            match self.get(point) {
+                Some(cell) if include(cell) && seen.insert(point) => {
+                    region.push(point);
+                    to_visit.extend(self.neighbours4(point));
+                }
+                _ => {}
+            }
+        }
+        region
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    // Draws each cell as a character, one line per row
+    pub fn render(&self, mut f: impl FnMut(Point, &T) -> char) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for (point, cell) in self.iter() {
+            if point.column == 0 && point.row > 0 {
+                out.push('\n');
+            }
+            out.push(f(point, cell));
+        }
+        out
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Grid::parse_with(s, Ok)
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        self.get(point)
+            .unwrap_or_else(|| panic!("{} is outside the grid", point))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        self.get_mut(point)
+            .unwrap_or_else(|| panic!("{} is outside the grid", point))
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+static TEST_GRID: &str = "#..#
+.##.
+#..#";
+
+#[test]
+fn test_parse() {
+    let grid = TEST_GRID.parse::<Grid<char>>().unwrap();
+    assert_eq!(grid.width(), 4);
+    assert_eq!(grid.height(), 3);
+    assert_eq!(grid.get(Point::new(1, 1)), Some(&'#'));
+    assert_eq!(grid.get(Point::new(2, 1)), Some(&'.'));
+    assert_eq!(grid.get(Point::new(3, 0)), None);
+    assert_eq!(grid.get(Point::new(0, 4)), None);
+    assert_eq!(grid.to_string(), TEST_GRID);
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |input: &str| input.parse::<Grid<char>>().unwrap_err().to_string();
+    assert_eq!(
+        error(""),
+        "Parse error at line 1, column 1: the grid is empty
+1 |
+  | ^"
+    );
+    assert_eq!(
+        error("#..\n#.\n..."),
+        "Parse error at line 2, column 3: expected 3 columns but found 2
+2 | #.
+  |   ^"
+    );
+    assert_eq!(
+        Grid::parse_with("01\n2x", |c| c.to_digit(10).ok_or("not a digit".to_owned()))
+            .unwrap_err()
+            .to_string(),
+        "Parse error at line 2, column 2: not a digit
+2 | 2x
+  |  ^"
+    );
+}
+
+#[test]
+fn test_rows_and_columns() {
+    let grid = TEST_GRID.parse::<Grid<char>>().unwrap();
+    assert_eq!(grid.row(1), Some(&['.', '#', '#', '.'][..]));
+    assert_eq!(grid.row(3), None);
+    assert_eq!(grid.rows().count(), 3);
+    assert_eq!(grid.column(3).collect::<String>(), "#.#");
+    assert_eq!(grid.column(4).count(), 0);
+    assert_eq!(
+        grid.columns()
+            .map(|c| c.collect::<String>())
+            .collect::<Vec<_>>(),
+        vec!["#.#", ".#.", ".#.", "#.#"]
+    );
+}
+
+#[test]
+fn test_no_columns() {
+    let grid = Grid::new(0, 3, 0);
+    assert_eq!(grid.rows().collect::<Vec<_>>(), vec![&[] as &[i32]; 3]);
+    assert_eq!(grid.column(0).count(), 0);
+    assert_eq!(grid.columns().count(), 0);
+    assert_eq!(grid.points().count(), 0);
+}
+
+#[test]
+fn test_neighbours() {
+    let grid = Grid::new(3, 3, 0);
+    assert_eq!(
+        grid.neighbours4(Point::new(0, 0)).collect::<Vec<_>>(),
+        vec![Point::new(0, 1), Point::new(1, 0)]
+    );
+    assert_eq!(grid.neighbours4(Point::new(1, 1)).count(), 4);
+    assert_eq!(
+        grid.neighbours8(Point::new(2, 2)).collect::<Vec<_>>(),
+        vec![Point::new(1, 1), Point::new(1, 2), Point::new(2, 1)]
+    );
+    assert_eq!(grid.neighbours8(Point::new(1, 1)).count(), 8);
+}
+
+#[test]
+fn test_regions() {
+    let grid = TEST_GRID.parse::<Grid<char>>().unwrap();
+    let mut middle = grid.region(Point::new(1, 1), |&c| c == '#');
+    middle.sort();
+    assert_eq!(middle, vec![Point::new(1, 1), Point::new(1, 2)]);
+    assert_eq!(grid.region(Point::new(0, 1), |&c| c == '#'), vec![]);
+
+    assert_eq!(grid.regions(|&c| c == '#').len(), 5);
+    let dots = grid.regions(|&c| c == '.');
+    assert_eq!(dots.len(), 4);
+    assert_eq!(dots.iter().map(Vec::len).sum::<usize>(), 6);
+}
+
+#[test]
+fn test_render() {
+    let mut grid = TEST_GRID.parse::<Grid<char>>().unwrap();
+    grid[Point::new(0, 1)] = 'x';
+    assert_eq!(
+        grid.render(|p, &c| if p.row == 2 { '~' } else { c }),
+        "#x.#\n.##.\n~~~~"
+    );
+    assert_eq!(grid.map(|&c| c == '#').get(Point::new(0, 0)), Some(&true));
+}
@@ -0,0 +1,80 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::daylib::{Error, Result};
+
+pub const INPUTS_DIR_VAR: &str = "AOC_INPUTS";
+const DEFAULT_INPUTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/inputs");
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl InputSource {
+    // An explicit path wins, "-" means stdin, otherwise look for dayN.txt in the inputs directory
+    pub fn for_day(day: u8, path: Option<&str>, inputs_dir: &Path) -> Self {
+        match path {
+            Some("-") => InputSource::Stdin,
+            Some(path) => InputSource::File(PathBuf::from(path)),
+            None => InputSource::File(inputs_dir.join(format!("day{}.txt", day))),
+        }
+    }
+
+    pub fn read(&self) -> Result<String> {
+        match self {
+            InputSource::Stdin => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                Ok(input)
+            }
+            InputSource::File(path) => fs::read_to_string(path).map_err(|e| {
+                if e.kind() == io::ErrorKind::NotFound {
+                    Error::MissingInput(path.clone())
+                } else {
+                    Error::Io(e)
+                }
+            }),
+        }
+    }
+}
+
+pub fn default_inputs_dir() -> PathBuf {
+    env::var_os(INPUTS_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_INPUTS_DIR))
+}
+
+#[test]
+fn test_source_for_day() {
+    let dir = Path::new("inputs");
+    assert_eq!(
+        InputSource::for_day(3, None, dir),
+        InputSource::File(PathBuf::from("inputs/day3.txt"))
+    );
+    assert_eq!(
+        InputSource::for_day(3, Some("mine.txt"), dir),
+        InputSource::File(PathBuf::from("mine.txt"))
+    );
+    assert_eq!(InputSource::for_day(3, Some("-"), dir), InputSource::Stdin);
+}
+
+#[test]
+fn test_missing_input() {
+    let source = InputSource::for_day(1, None, Path::new("definitely/not/here"));
+    let error = source.read().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Input file definitely/not/here/day1.txt does not exist"
+    );
+}
+
+#[test]
+fn test_default_inputs_dir_has_day1() {
+    let source = InputSource::for_day(1, None, Path::new(DEFAULT_INPUTS_DIR));
+    assert!(!source.read().unwrap().is_empty());
+}
@@ -0,0 +1,224 @@
+use std::ops::Range;
+
+// A set of values stored as sorted ranges, which never overlap or touch and are never empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // everything from first to last overlaps or touches the new range
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        if first == last {
+            self.ranges.insert(first, range);
+        } else {
+            let start = T::min(range.start, self.ranges[first].start);
+            let end = T::max(range.end, self.ranges[last - 1].end);
+            self.ranges.splice(first..last, [start..end]);
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(i).is_some_and(|r| r.contains(&value))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for range in &other.ranges {
+            union.insert(range.clone());
+        }
+        union
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = T::max(a.start, b.start);
+            let end = T::min(a.end, b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            // skip the ranges of other which end before this one starts
+            while other.ranges.get(j).is_some_and(|r| r.end <= start) {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(cut) = other.ranges.get(k).filter(|r| r.start < range.end) {
+                if start < cut.start {
+                    ranges.push(start..cut.start);
+                }
+                start = T::max(start, cut.end);
+                k += 1;
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+impl IntervalSet<u64> {
+    pub fn max(&self) -> Option<u64> {
+        self.ranges.last().map(|r| r.end - 1)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    // Moves every value by offset, or None if that would leave the u64s
+    pub fn shift(&self, offset: i128) -> Option<Self> {
+        let shift = |v: u64| u64::try_from(v as i128 + offset).ok();
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|r| Some(shift(r.start)?..shift(r.end)?))
+            .collect::<Option<Vec<_>>>()?;
+        Some(IntervalSet { ranges })
+    }
+}
+
+impl<T: Copy + Ord> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut ranges = iter
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|r| r.start);
+
+        let mut merged: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = T::max(last.end, range.end),
+                _ => merged.push(range),
+            }
+        }
+        IntervalSet { ranges: merged }
+    }
+}
+
+impl<T: Copy + Ord> Extend<Range<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+#[cfg(test)]
+fn set(ranges: &[Range<u64>]) -> IntervalSet<u64> {
+    ranges.iter().cloned().collect()
+}
+
+#[test]
+fn test_normalise() {
+    assert_eq!(set(&[8..9, 1..3, 2..4, 4..5, 7..7]).ranges(), [1..5, 8..9]);
+    assert!(IntervalSet::from(3..3).is_empty());
+
+    let mut inserted = set(&[1..3, 5..7, 9..11]);
+    inserted.insert(3..5);
+    assert_eq!(inserted.ranges(), [1..7, 9..11]);
+    inserted.insert(12..13);
+    inserted.insert(0..1);
+    inserted.insert(8..8);
+    assert_eq!(inserted.ranges(), [0..7, 9..11, 12..13]);
+    inserted.insert(2..20);
+    assert_eq!(inserted, IntervalSet::from(0..20));
+    inserted.extend([30..31, 25..30]);
+    assert_eq!(inserted.ranges(), [0..20, 25..31]);
+}
+
+#[test]
+fn test_set_operations() {
+    let a = set(&[0..10, 20..30]);
+    let b = set(&[5..25, 28..40]);
+    assert_eq!(a.union(&b), IntervalSet::from(0..40));
+    assert_eq!(a.intersection(&b).ranges(), [5..10, 20..25, 28..30]);
+    assert_eq!(a.difference(&b).ranges(), [0..5, 25..28]);
+    assert_eq!(b.difference(&a).ranges(), [10..20, 30..40]);
+    assert_eq!(a.difference(&a), IntervalSet::new());
+    assert_eq!(a.intersection(&IntervalSet::new()), IntervalSet::new());
+    assert_eq!(
+        IntervalSet::from(0..100)
+            .difference(&set(&[10..20, 30..40]))
+            .ranges(),
+        [0..10, 20..30, 40..100]
+    );
+}
+
+#[test]
+fn test_queries() {
+    let a = set(&[3..6, 10..11]);
+    assert!(!a.contains(2));
+    assert!(a.contains(3));
+    assert!(a.contains(5));
+    assert!(!a.contains(6));
+    assert!(a.contains(10));
+    assert!(!a.contains(11));
+    assert_eq!(a.min(), Some(3));
+    assert_eq!(a.max(), Some(10));
+    assert_eq!(a.len(), 4);
+    assert_eq!(IntervalSet::<u64>::new().max(), None);
+}
+
+#[test]
+fn test_shift() {
+    let a = set(&[3..6, 10..11]);
+    assert_eq!(a.shift(10), Some(set(&[13..16, 20..21])));
+    assert_eq!(a.shift(-3), Some(set(&[0..3, 7..8])));
+    assert_eq!(a.shift(-4), None);
+    assert_eq!(IntervalSet::from(u64::MAX - 1..u64::MAX).shift(1), None);
+}
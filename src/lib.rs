@@ -0,0 +1,17 @@
+mod day1;
+mod day2;
+mod day3;
+mod day4;
+mod day5;
+
+pub mod bench;
+pub mod daylib;
+pub mod error;
+pub mod grid;
+pub mod input;
+pub mod interval;
+pub mod json;
+pub mod registry;
+pub mod runner;
+pub mod trace;
+pub mod verify;
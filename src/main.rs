@@ -1,32 +1,251 @@
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod daylib;
+use adventofcode2023::{
+    bench,
+    daylib::{Day, Format, Timings},
+    error::Error as AocError,
+    input::{self, InputSource},
+    registry,
+    runner::{self, Selection},
+    trace::{self, Level},
+    verify::{self, Answers},
+};
+use std::{
+    env::args,
+    error::Error,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use std::{env::args, error::Error};
+const USAGE: &str = "Usage: adventofcode2023 [--inputs <dir>] [--bench <runs>] [--answers <file>] [--format text|json] [--strict] [--report] [-v | -vv] (list | verify [<days>] | all | <first>..=<last> | <day> [<input file> | -])";
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut args = args();
-    if args.len() < 2 {
-        return Err("Bad! Must give day number argument!".into());
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut inputs_dir = input::default_inputs_dir();
+    let mut bench_runs = None;
+    let mut answers_file = None;
+    let mut json = false;
+    let mut strict = false;
+    let mut report = false;
+    let mut verbosity = 0;
+    let mut positional = Vec::new();
+
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--inputs" => {
+                inputs_dir = PathBuf::from(args.next().ok_or("Bad! --inputs needs a directory!")?);
+            }
+            "--bench" => {
+                let runs = args.next().ok_or("Bad! --bench needs a number of runs!")?;
+                bench_runs = Some(runs.parse::<usize>()?);
+            }
+            "--answers" => {
+                answers_file = Some(PathBuf::from(
+                    args.next().ok_or("Bad! --answers needs a file!")?,
+                ));
+            }
+            "--format" => {
+                json = match args.next().as_deref() {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => return Err("Bad! --format needs to be text or json!".into()),
+                };
+            }
+            "--strict" => strict = true,
+            "--report" => report = true,
+            "-v" | "--verbose" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            _ => positional.push(arg),
+        }
+    }
+
+    trace::set_level(Level::from_count(verbosity));
+
+    if positional.is_empty() || positional.len() > 2 {
+        return Err(format!("Bad! Must give day number argument!\n{}", USAGE).into());
+    }
+
+    if positional[0] == "list" {
+        list_days();
+        return Ok(());
+    }
+
+    let verifying = positional[0] == "verify";
+    // combinations where one of the flags would otherwise be silently ignored
+    if json && (bench_runs.is_some() || verifying) {
+        return Err("Bad! --format json doesn't work with --bench or verify!".into());
+    }
+    if (strict || report) && verifying {
+        return Err("Bad! --strict and --report don't work with verify!".into());
+    }
+    if bench_runs.is_some() && (report || verifying) {
+        return Err("Bad! --bench doesn't work with --report or verify!".into());
+    }
+
+    if verifying {
+        let selection = match positional.get(1) {
+            Some(days) => days.parse::<Selection>()?,
+            None => Selection::All,
+        };
+        let answers_file = answers_file.unwrap_or_else(|| inputs_dir.join(verify::ANSWERS_FILE));
+        return run_verify(&selection, &inputs_dir, &answers_file);
+    }
+
+    let selection = positional[0].parse::<Selection>()?;
+    let day_number = match selection {
+        Selection::One(day_number) => day_number,
+        _ if positional.len() > 1 => {
+            return Err("Bad! An input file can only be given when running one day!".into());
+        }
+        _ if strict || report => {
+            return Err("Bad! --strict and --report only work when running one day!".into());
+        }
+        _ => {
+            return match bench_runs {
+                Some(runs) => run_bench(&selection.day_numbers(registry::YEAR), runs, &inputs_dir),
+                None => run_many(&selection, &inputs_dir, json),
+            };
+        }
+    };
+
+    let source = InputSource::for_day(
+        day_number,
+        positional.get(1).map(String::as_str),
+        &inputs_dir,
+    );
+
+    let day = registry::find(registry::YEAR, day_number).ok_or(AocError::UnsupportedDay {
+        year: registry::YEAR,
+        day: day_number,
+    })?;
+
+    let input = source.read();
+    if let (true, Ok(input)) = (strict, &input) {
+        check_input(day, input)?;
+    }
+
+    if report {
+        let report = day.report.ok_or("Bad! This day doesn't have a report!")?;
+        let format = if json { Format::Json } else { Format::Text };
+        println!("{}", report(&input?, format)?);
+        return Ok(());
+    }
+
+    if json {
+        let run = runner::run_input(day, input);
+        println!("{}", runner::json_report(std::slice::from_ref(&run)));
+        run.result?;
+        return Ok(());
     }
 
-    let day_number: u8 = args.nth(1).unwrap().parse::<u8>()?;
+    let input = input?;
 
-    let result = match day_number {
-        1 => day1::day1(include_str!("inputs/day1.txt")),
-        2 => day2::day2(include_str!("inputs/day2.txt")),
-        3 => day3::day3(include_str!("inputs/day3.txt")),
-        4 => day4::day4(include_str!("inputs/day4.txt")),
-        5 => day5::day5(include_str!("inputs/day5.txt")),
-        _ => Err(format!("Bad! I don't know how to run day {}!", day_number).into()),
-    }?;
+    if let Some(runs) = bench_runs {
+        println!(
+            "{}",
+            bench::bench_table(&[bench::bench_day(day, &input, runs)?])
+        );
+        return Ok(());
+    }
+
+    let mut timings = Timings::default();
+    let result = (day.run)(&input, &mut timings)?;
 
     println!("Result for day {}:", day_number);
     println!("{}", result.part1);
     println!("{}", result.part2);
+    println!(
+        "Parsed in {:.2?}, part 1 in {:.2?}, part 2 in {:.2?}",
+        timings.parse, timings.part1, timings.part2
+    );
+
+    Ok(())
+}
+
+fn list_days() {
+    println!("Available days for {}:", registry::YEAR);
+    for day in registry::days().iter().filter(|d| d.year == registry::YEAR) {
+        println!("  day {}", day.day);
+    }
+
+    let missing = registry::missing(registry::YEAR);
+    if !missing.is_empty() {
+        println!("Missing days: {:?}", missing);
+    }
+}
+
+// Days without a stricter check only get the usual parsing when they run
+fn check_input(day: &Day, input: &str) -> Result<(), Box<dyn Error>> {
+    let problems = day.check.map(|check| check(input)).unwrap_or_default();
+    for problem in &problems {
+        eprintln!("{}\n", problem);
+    }
+
+    if !problems.is_empty() {
+        return Err(format!(
+            "{} problems in the input for day {}",
+            problems.len(),
+            day.day
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn run_many(selection: &Selection, inputs_dir: &Path, json: bool) -> Result<(), Box<dyn Error>> {
+    let runs = runner::run_selection(selection, registry::YEAR, inputs_dir);
+    if json {
+        println!("{}", runner::json_report(&runs));
+    } else {
+        println!("{}", runner::summary_table(&runs));
+    }
+
+    let failures = runs.iter().filter(|r| r.result.is_err()).count();
+    if failures > 0 {
+        return Err(format!("{} of {} days failed", failures, runs.len()).into());
+    }
+
+    Ok(())
+}
+
+fn run_bench(day_numbers: &[u8], runs: usize, inputs_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut benches = Vec::new();
+    for &day_number in day_numbers {
+        let day = registry::find(registry::YEAR, day_number).ok_or(AocError::UnsupportedDay {
+            year: registry::YEAR,
+            day: day_number,
+        })?;
+        let input = InputSource::for_day(day_number, None, inputs_dir).read()?;
+        benches.push(bench::bench_day(day, &input, runs)?);
+    }
+
+    println!("{}", bench::bench_table(&benches));
+    Ok(())
+}
+
+fn run_verify(
+    selection: &Selection,
+    inputs_dir: &Path,
+    answers_file: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let answers = Answers::load(answers_file)?;
+    let runs = runner::run_selection(selection, registry::YEAR, inputs_dir);
+    let checks = verify::verify(&runs, &answers);
+    println!("{}", verify::verify_table(&checks));
+
+    let failures = checks.iter().filter(|c| c.failed()).count();
+    if failures > 0 {
+        return Err(format!("{} of {} checks failed", failures, checks.len()).into());
+    }
 
     Ok(())
 }
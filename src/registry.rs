@@ -0,0 +1,56 @@
+use crate::{day1, day2, day3, day4, day5, daylib::Day};
+
+pub const YEAR: u16 = 2023;
+
+static DAYS: &[Day] = &[day1::DAY, day2::DAY, day3::DAY, day4::DAY, day5::DAY];
+
+pub fn days() -> &'static [Day] {
+    DAYS
+}
+
+pub fn find(year: u16, day: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.year == year && d.day == day)
+}
+
+// Days before the last registered one which have no solver
+pub fn missing(year: u16) -> Vec<u8> {
+    gaps(DAYS.iter().filter(|d| d.year == year).map(|d| d.day))
+}
+
+fn gaps(days: impl Iterator<Item = u8>) -> Vec<u8> {
+    let mut days = days.collect::<Vec<u8>>();
+    days.sort_unstable();
+    let last = days.last().copied().unwrap_or(0);
+    (1..=last)
+        .filter(|d| days.binary_search(d).is_err())
+        .collect()
+}
+
+#[test]
+fn test_registry_is_unique() {
+    for (i, a) in DAYS.iter().enumerate() {
+        for b in &DAYS[i + 1..] {
+            assert!(
+                a.year != b.year || a.day != b.day,
+                "Day {} of {} registered twice",
+                a.day,
+                a.year
+            );
+        }
+    }
+}
+
+#[test]
+fn test_find() {
+    assert_eq!(find(YEAR, 4).map(|d| d.day), Some(4));
+    assert!(find(YEAR, 26).is_none());
+    assert!(find(2022, 1).is_none());
+}
+
+#[test]
+fn test_gaps() {
+    assert_eq!(gaps([1, 2, 3].into_iter()), Vec::<u8>::new());
+    assert_eq!(gaps([5, 1, 3].into_iter()), vec![2, 4]);
+    assert_eq!(gaps(std::iter::empty()), Vec::<u8>::new());
+    assert_eq!(missing(YEAR), Vec::<u8>::new());
+}
@@ -0,0 +1,295 @@
+use std::{path::Path, str::FromStr, time::Duration};
+
+use crate::{
+    daylib::{self, parse_number, Answer, Day, Error, Parts, Timings},
+    input::InputSource,
+    json::Json,
+    registry,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Selection {
+    One(u8),
+    Range(u8, u8),
+    All,
+}
+
+impl FromStr for Selection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> daylib::Result<Self> {
+        if s == "all" {
+            return Ok(Selection::All);
+        }
+
+        if let Some((start, end)) = s.split_once("..") {
+            let empty = || Error::parse(1, 1, s, "range is empty");
+            let start = parse_number::<u8>(start, 1, s)?;
+            let end = match end.strip_prefix('=') {
+                Some(end) => parse_number::<u8>(end, s.len() - end.len() + 1, s)?,
+                None => parse_number::<u8>(end, s.len() - end.len() + 1, s)?
+                    .checked_sub(1)
+                    .ok_or_else(empty)?,
+            };
+            if start > end {
+                return Err(empty());
+            }
+            return Ok(Selection::Range(start, end));
+        }
+
+        Ok(Selection::One(parse_number(s, 1, s)?))
+    }
+}
+
+impl Selection {
+    // Every day number asked for, whether or not there is a solver for it
+    pub fn day_numbers(&self, year: u16) -> Vec<u8> {
+        match self {
+            Selection::One(day) => vec![*day],
+            Selection::Range(start, end) => (*start..=*end).collect(),
+            Selection::All => registry::days()
+                .iter()
+                .filter(|d| d.year == year)
+                .map(|d| d.day)
+                .collect(),
+        }
+    }
+}
+
+pub struct DayRun {
+    pub day: u8,
+    pub timings: Timings,
+    pub result: daylib::Result<Parts>,
+}
+
+pub fn run_day(day: &Day, source: &InputSource) -> DayRun {
+    run_input(day, source.read())
+}
+
+// For callers which have already read the input, so stdin isn't read twice
+pub fn run_input(day: &Day, input: daylib::Result<String>) -> DayRun {
+    let mut timings = Timings::default();
+    let result = input.and_then(|input| (day.run)(&input, &mut timings));
+    DayRun {
+        day: day.day,
+        timings,
+        result,
+    }
+}
+
+pub fn run_selection(selection: &Selection, year: u16, inputs_dir: &Path) -> Vec<DayRun> {
+    selection
+        .day_numbers(year)
+        .into_iter()
+        .map(|day_number| match registry::find(year, day_number) {
+            Some(day) => run_day(day, &InputSource::for_day(day_number, None, inputs_dir)),
+            None => DayRun {
+                day: day_number,
+                timings: Timings::default(),
+                result: Err(Error::UnsupportedDay {
+                    year,
+                    day: day_number,
+                }),
+            },
+        })
+        .collect()
+}
+
+pub fn summary_table(runs: &[DayRun]) -> String {
+    let mut rows = vec![["Day", "Part 1", "Part 2", "Parse", "Time 1", "Time 2"].map(String::from)];
+    for run in runs {
+        let (part1, part2) = match &run.result {
+            Ok(parts) => (
+                parts.part1.answer.to_string(),
+                parts.part2.answer.to_string(),
+            ),
+            // a parse error's snippet would break up the table
+            Err(e) => (
+                format!("ERROR: {}", e.to_string().lines().next().unwrap_or("")),
+                String::new(),
+            ),
+        };
+        rows.push([
+            run.day.to_string(),
+            part1,
+            part2,
+            format!("{:.2?}", run.timings.parse),
+            format!("{:.2?}", run.timings.part1),
+            format!("{:.2?}", run.timings.part2),
+        ]);
+    }
+
+    table(&rows)
+}
+
+// One object per day and part, so a failing day still reports both of its parts
+pub fn json_report(runs: &[DayRun]) -> Json {
+    let mut records = Vec::new();
+    for run in runs {
+        for (part, elapsed) in [(1, run.timings.part1), (2, run.timings.part2)] {
+            let (answer, description, error) = match &run.result {
+                Ok(parts) => {
+                    let part = if part == 1 {
+                        &parts.part1
+                    } else {
+                        &parts.part2
+                    };
+                    (
+                        answer_json(&part.answer),
+                        part.description.clone().into(),
+                        Json::Null,
+                    )
+                }
+                Err(e) => (Json::Null, Json::Null, Json::string(e.to_string())),
+            };
+            records.push(Json::object([
+                ("day", Json::number(run.day)),
+                ("part", Json::number(part)),
+                ("answer", answer),
+                ("description", description),
+                ("parse_ms", millis(run.timings.parse)),
+                ("elapsed_ms", millis(elapsed)),
+                ("error", error),
+            ]));
+        }
+    }
+    Json::Array(records)
+}
+
+fn answer_json(answer: &Answer) -> Json {
+    match answer {
+        Answer::Integer(i) => Json::number(i),
+        Answer::Text(s) => Json::string(s.as_str()),
+    }
+}
+
+fn millis(d: Duration) -> Json {
+    Json::number(format!("{:.3}", d.as_secs_f64() * 1000.0))
+}
+
+pub(crate) fn table<const N: usize>(rows: &[[String; N]]) -> String {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = usize::max(*width, cell.chars().count());
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<String>>()
+                .join(" | ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[test]
+fn test_parse_selection() {
+    assert_eq!(Selection::from_str("all").unwrap(), Selection::All);
+    assert_eq!(Selection::from_str("4").unwrap(), Selection::One(4));
+    assert_eq!(
+        Selection::from_str("1..=5").unwrap(),
+        Selection::Range(1, 5)
+    );
+    assert_eq!(Selection::from_str("1..5").unwrap(), Selection::Range(1, 4));
+    assert!(Selection::from_str("5..=1").is_err());
+    assert!(Selection::from_str("3..3").is_err());
+    assert_eq!(
+        Selection::from_str("1..=x").unwrap_err().to_string(),
+        "Parse error at line 1, column 5: 'x' is not a number: invalid digit found in string
+1 | 1..=x
+  |     ^"
+    );
+}
+
+#[test]
+fn test_day_numbers() {
+    assert_eq!(Selection::Range(4, 7).day_numbers(2023), vec![4, 5, 6, 7]);
+    assert_eq!(
+        Selection::All.day_numbers(2023),
+        registry::days().iter().map(|d| d.day).collect::<Vec<u8>>()
+    );
+}
+
+#[test]
+fn test_unknown_day_is_an_error() {
+    let runs = run_selection(&Selection::One(26), 2023, Path::new("nowhere"));
+    assert_eq!(runs.len(), 1);
+    assert_eq!(
+        runs[0].result.as_ref().err().unwrap().to_string(),
+        "No solver for day 26 of 2023"
+    );
+}
+
+#[test]
+fn test_json_report() {
+    use crate::daylib::Part;
+
+    let runs = vec![
+        DayRun {
+            day: 1,
+            timings: Timings {
+                parse: Duration::from_micros(250),
+                part1: Duration::from_millis(3),
+                part2: Duration::ZERO,
+            },
+            result: Ok(Parts {
+                part1: Part::described(142u32, "The sum of the digits"),
+                part2: Part::new("Not implemented"),
+            }),
+        },
+        DayRun {
+            day: 6,
+            timings: Timings::default(),
+            result: Err("No solver for day 6".into()),
+        },
+    ];
+
+    assert_eq!(
+        json_report(&runs).to_string(),
+        concat!(
+            r#"[{"day":1,"part":1,"answer":142,"description":"The sum of the digits","parse_ms":0.250,"elapsed_ms":3.000,"error":null},"#,
+            r#"{"day":1,"part":2,"answer":"Not implemented","description":null,"parse_ms":0.250,"elapsed_ms":0.000,"error":null},"#,
+            r#"{"day":6,"part":1,"answer":null,"description":null,"parse_ms":0.000,"elapsed_ms":0.000,"error":"No solver for day 6"},"#,
+            r#"{"day":6,"part":2,"answer":null,"description":null,"parse_ms":0.000,"elapsed_ms":0.000,"error":"No solver for day 6"}]"#
+        )
+    );
+}
+
+#[test]
+fn test_summary_table() {
+    use crate::daylib::Part;
+
+    let runs = vec![
+        DayRun {
+            day: 1,
+            timings: Timings {
+                parse: Duration::from_micros(250),
+                part1: Duration::from_millis(3),
+                part2: Duration::from_secs(1),
+            },
+            result: Ok(Parts {
+                part1: Part::described(142u32, "The sum of the digits"),
+                part2: Part::new(281u32),
+            }),
+        },
+        DayRun {
+            day: 12,
+            timings: Timings::default(),
+            result: Err("No solver for day 12".into()),
+        },
+    ];
+
+    assert_eq!(
+        summary_table(&runs),
+        "Day | Part 1                      | Part 2 | Parse    | Time 1 | Time 2
+1   | 142                         | 281    | 250.00µs | 3.00ms | 1.00s
+12  | ERROR: No solver for day 12 |        | 0.00ns   | 0.00ns | 0.00ns"
+    );
+}
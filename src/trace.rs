@@ -0,0 +1,87 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+// How much the solvers say about what they're doing, on stderr so answers stay clean
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Quiet,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    // One -v for debug, two or more for trace
+    pub fn from_count(count: usize) -> Self {
+        match count {
+            0 => Level::Quiet,
+            1 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Quiet as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> Level {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => Level::Quiet,
+        1 => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    level != Level::Quiet && level <= self::level()
+}
+
+// Tags each line with the module it came from, so "adventofcode2023::day5" shows as day5
+pub fn emit(level: Level, module: &str, args: fmt::Arguments) {
+    let module = module.rsplit("::").next().unwrap_or(module);
+    let level = match level {
+        Level::Trace => "trace",
+        _ => "debug",
+    };
+    eprintln!("[{} {}] {}", module, level, args);
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::Level::Debug) {
+            $crate::trace::emit($crate::trace::Level::Debug, module_path!(), format_args!($($arg)*));
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::Level::Trace) {
+            $crate::trace::emit($crate::trace::Level::Trace, module_path!(), format_args!($($arg)*));
+        }
+    };
+}
+
+#[test]
+fn test_levels() {
+    assert_eq!(Level::from_count(0), Level::Quiet);
+    assert_eq!(Level::from_count(1), Level::Debug);
+    assert_eq!(Level::from_count(5), Level::Trace);
+
+    assert!(!enabled(Level::Debug));
+    set_level(Level::Debug);
+    assert!(enabled(Level::Debug));
+    assert!(!enabled(Level::Trace));
+    assert!(!enabled(Level::Quiet));
+    set_level(Level::Trace);
+    assert!(enabled(Level::Debug));
+    assert!(enabled(Level::Trace));
+    set_level(Level::Quiet);
+    assert!(!enabled(Level::Debug));
+}
@@ -0,0 +1,237 @@
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+use crate::{
+    daylib::{parse_number, words, Answer, Error, Result},
+    runner::{table, DayRun},
+};
+
+pub const ANSWERS_FILE: &str = "answers.txt";
+
+// Known answers keyed by (day, part), read from lines of "<day> <part> <answer>"
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Answers(BTreeMap<(u8, u8), Answer>);
+
+impl FromStr for Answers {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut answers = BTreeMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line_number = i + 1;
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let mut fields = words(line);
+            let (Some((day_column, day)), Some((part_column, part)), Some((answer_column, _))) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(Error::parse(
+                    line_number,
+                    1,
+                    line,
+                    "expected '<day> <part> <answer>'",
+                ));
+            };
+            let day =
+                parse_number::<u8>(day, day_column, line).map_err(|e| e.on_line(line_number))?;
+            let part = match part {
+                "1" => 1,
+                "2" => 2,
+                _ => {
+                    return Err(Error::parse(
+                        line_number,
+                        part_column,
+                        line,
+                        "part must be 1 or 2",
+                    ))
+                }
+            };
+            let answer = line[answer_column - 1..].trim_end().parse::<Answer>()?;
+
+            if answers.insert((day, part), answer).is_some() {
+                return Err(Error::parse(
+                    line_number,
+                    day_column,
+                    line,
+                    format!("day {} part {} already has an answer", day, part),
+                ));
+            }
+        }
+
+        Ok(Answers(answers))
+    }
+}
+
+impl Answers {
+    pub fn load(path: &Path) -> Result<Self> {
+        fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read answers file {}: {}", path.display(), e))?
+            .parse()
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&Answer> {
+        self.0.get(&(day, part))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail { expected: Answer, actual: Answer },
+    Missing(Answer),
+    Error(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Check {
+    pub day: u8,
+    pub part: u8,
+    pub outcome: Outcome,
+}
+
+impl Check {
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Fail { .. } | Outcome::Error(_))
+    }
+}
+
+pub fn verify(runs: &[DayRun], answers: &Answers) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for run in runs {
+        for part in [1, 2] {
+            let outcome = match &run.result {
+                Err(e) => Outcome::Error(e.to_string()),
+                Ok(parts) => {
+                    let actual = if part == 1 {
+                        &parts.part1
+                    } else {
+                        &parts.part2
+                    };
+                    match answers.get(run.day, part) {
+                        None => Outcome::Missing(actual.answer.clone()),
+                        Some(expected) if *expected == actual.answer => Outcome::Pass,
+                        Some(expected) => Outcome::Fail {
+                            expected: expected.clone(),
+                            actual: actual.answer.clone(),
+                        },
+                    }
+                }
+            };
+            checks.push(Check {
+                day: run.day,
+                part,
+                outcome,
+            });
+        }
+    }
+    checks
+}
+
+pub fn verify_table(checks: &[Check]) -> String {
+    let mut rows = vec![["Day", "Part", "Result", "Detail"].map(String::from)];
+    for check in checks {
+        let (result, detail) = match &check.outcome {
+            Outcome::Pass => ("pass", String::new()),
+            Outcome::Fail { expected, actual } => {
+                ("FAIL", format!("expected {}, got {}", expected, actual))
+            }
+            Outcome::Missing(actual) => ("missing", format!("no known answer, got {}", actual)),
+            Outcome::Error(e) => ("ERROR", e.lines().next().unwrap_or("").to_owned()),
+        };
+        rows.push([
+            check.day.to_string(),
+            check.part.to_string(),
+            result.to_owned(),
+            detail,
+        ]);
+    }
+
+    table(&rows)
+}
+
+#[test]
+fn test_parse_answers() {
+    let answers = "# day part answer
+1 1 142
+1 2 281
+
+3 2 some text answer"
+        .parse::<Answers>()
+        .unwrap();
+
+    assert_eq!(answers.get(1, 1), Some(&Answer::Integer(142)));
+    assert_eq!(answers.get(1, 2), Some(&Answer::Integer(281)));
+    assert_eq!(
+        answers.get(3, 2),
+        Some(&Answer::Text("some text answer".to_owned()))
+    );
+    assert_eq!(answers.get(2, 1), None);
+
+    let error = |s: &str| s.parse::<Answers>().unwrap_err().to_string();
+    assert_eq!(
+        error("1 3 142"),
+        "Parse error at line 1, column 3: part must be 1 or 2
+1 | 1 3 142
+  |   ^"
+    );
+    assert_eq!(
+        error("1 1"),
+        "Parse error at line 1, column 1: expected '<day> <part> <answer>'
+1 | 1 1
+  | ^"
+    );
+    assert_eq!(
+        error("1 1 142\n1 1 143"),
+        "Parse error at line 2, column 1: day 1 part 1 already has an answer
+2 | 1 1 143
+  | ^"
+    );
+}
+
+#[test]
+fn test_verify() {
+    use crate::daylib::{Part, Parts, Timings};
+
+    let answers = "1 1 142\n1 2 280\n2 1 8".parse::<Answers>().unwrap();
+    let runs = [
+        DayRun {
+            day: 1,
+            timings: Timings::default(),
+            result: Ok(Parts {
+                part1: Part::new(142u32),
+                part2: Part::new(281u32),
+            }),
+        },
+        DayRun {
+            day: 2,
+            timings: Timings::default(),
+            result: Err("Bad input".into()),
+        },
+    ];
+
+    let checks = verify(&runs, &answers);
+    let outcomes = checks.iter().map(|c| &c.outcome).collect::<Vec<_>>();
+    assert_eq!(
+        outcomes,
+        vec![
+            &Outcome::Pass,
+            &Outcome::Fail {
+                expected: Answer::Integer(280),
+                actual: Answer::Integer(281)
+            },
+            &Outcome::Error("Bad input".to_owned()),
+            &Outcome::Error("Bad input".to_owned()),
+        ]
+    );
+    assert_eq!(checks.iter().filter(|c| c.failed()).count(), 3);
+}
+
+#[test]
+fn test_real_answers_file_parses() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/inputs")
+        .join(ANSWERS_FILE);
+    let answers = Answers::load(&path).unwrap();
+    assert!(answers.get(1, 1).is_some());
+}